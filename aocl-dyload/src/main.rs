@@ -3,9 +3,35 @@
 
 use libloading::Library;
 use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
 use std::ffi::{c_char, c_int};
 use std::sync::Mutex;
 
+const CBLAS_ROW_MAJOR: c_int = 101;
+const CBLAS_COL_MAJOR: c_int = 102;
+const CBLAS_NO_TRANS: c_int = 111;
+const CBLAS_TRANS: c_int = 112;
+
+/// dgemm entry point used by `run_blas`: `fortran` (`dgemm_`), `cblas-col` or `cblas-row` (`cblas_dgemm`).
+static INTERFACE: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+
+pub type CblasDgemmFn = unsafe extern "C" fn(
+    layout: c_int,
+    transa: c_int,
+    transb: c_int,
+    m: c_int,
+    n: c_int,
+    k: c_int,
+    alpha: f64,
+    a: *const f64,
+    lda: c_int,
+    b: *const f64,
+    ldb: c_int,
+    beta: f64,
+    c: *mut f64,
+    ldc: c_int,
+);
+
 pub struct Lib {
     __library: Library,
     pub dgemm: unsafe extern "C" fn(
@@ -23,7 +49,27 @@ pub struct Lib {
         c: *mut f64,
         ldc: *const c_int,
     ),
+    pub dgemv: unsafe extern "C" fn(
+        trans: *const c_char,
+        m: *const c_int,
+        n: *const c_int,
+        alpha: *const f64,
+        a: *const f64,
+        lda: *const c_int,
+        x: *const f64,
+        incx: *const c_int,
+        beta: *const f64,
+        y: *mut f64,
+        incy: *const c_int,
+    ),
+    pub cblas_dgemm: CblasDgemmFn,
     pub bli_thread_get_num_threads: unsafe extern "C" fn() -> c_int,
+    pub bli_thread_set_ways: unsafe extern "C" fn(jc: i64, pc: i64, ic: i64, jr: i64, ir: i64),
+    pub bli_thread_get_jc_nt: unsafe extern "C" fn() -> i64,
+    pub bli_thread_get_pc_nt: unsafe extern "C" fn() -> i64,
+    pub bli_thread_get_ic_nt: unsafe extern "C" fn() -> i64,
+    pub bli_thread_get_jr_nt: unsafe extern "C" fn() -> i64,
+    pub bli_thread_get_ir_nt: unsafe extern "C" fn() -> i64,
     pub bli_thread_set_num_threads: unsafe extern "C" fn(n: c_int),
     pub dsyevd: unsafe extern "C" fn(
         jobz: *const c_char,
@@ -38,31 +84,66 @@ pub struct Lib {
         liwork: *mut c_int,
         info: *mut c_int,
     ),
+    pub omp_get_max_threads: unsafe extern "C" fn() -> c_int,
+    pub omp_set_num_threads: unsafe extern "C" fn(n: c_int),
 }
 
 impl Lib {
-    pub unsafe fn new<P>(path: P) -> Result<Self, ::libloading::Error>
+    pub unsafe fn new<P>(path: P, gomp_path: P) -> Result<Self, ::libloading::Error>
     where
         P: AsRef<::std::ffi::OsStr>,
     {
         let library = ::libloading::Library::new(path)?;
-        Self::from_library(library)
+        let gomp_library = ::libloading::Library::new(gomp_path)?;
+        Self::from_library(library, gomp_library)
     }
 
-    pub fn from_library(library: Library) -> Result<Self, ::libloading::Error> {
+    pub fn from_library(library: Library, gomp_library: Library) -> Result<Self, ::libloading::Error> {
         unsafe {
             let dgemm = library.get(b"dgemm_\0").map(|sym| *sym)?;
+            let dgemv = library.get(b"dgemv_\0").map(|sym| *sym)?;
+            let cblas_dgemm = library.get(b"cblas_dgemm\0").map(|sym| *sym)?;
             let bli_thread_get_num_threads = library.get(b"bli_thread_get_num_threads\0").map(|sym| *sym)?;
+            let bli_thread_set_ways = library.get(b"bli_thread_set_ways\0").map(|sym| *sym)?;
+            let bli_thread_get_jc_nt = library.get(b"bli_thread_get_jc_nt\0").map(|sym| *sym)?;
+            let bli_thread_get_pc_nt = library.get(b"bli_thread_get_pc_nt\0").map(|sym| *sym)?;
+            let bli_thread_get_ic_nt = library.get(b"bli_thread_get_ic_nt\0").map(|sym| *sym)?;
+            let bli_thread_get_jr_nt = library.get(b"bli_thread_get_jr_nt\0").map(|sym| *sym)?;
+            let bli_thread_get_ir_nt = library.get(b"bli_thread_get_ir_nt\0").map(|sym| *sym)?;
             let bli_thread_set_num_threads = library.get(b"bli_thread_set_num_threads\0").map(|sym| *sym)?;
             let dsyevd = library.get(b"dsyevd_\0").map(|sym| *sym)?;
-            Ok(Self { __library: library, dgemm, bli_thread_get_num_threads, bli_thread_set_num_threads, dsyevd })
+            let omp_get_max_threads = gomp_library.get(b"omp_get_max_threads\0").map(|sym| *sym)?;
+            let omp_set_num_threads = gomp_library.get(b"omp_set_num_threads\0").map(|sym| *sym)?;
+            Ok(Self {
+                __library: library,
+                dgemm,
+                dgemv,
+                cblas_dgemm,
+                bli_thread_get_num_threads,
+                bli_thread_set_ways,
+                bli_thread_get_jc_nt,
+                bli_thread_get_pc_nt,
+                bli_thread_get_ic_nt,
+                bli_thread_get_jr_nt,
+                bli_thread_get_ir_nt,
+                bli_thread_set_num_threads,
+                dsyevd,
+                omp_get_max_threads,
+                omp_set_num_threads,
+            })
         }
     }
 }
 
+/// Opens a new handle to the backend; `get_lib` keeps one for the whole process.
+pub unsafe fn load_lib() -> Lib {
+    // the gcc build of AOCL-BLAS is multithreaded with GNU OpenMP
+    Lib::new("/home/a/Software/aocl/5.1.0/gcc/lib/libflame.so", "libgomp.so").unwrap()
+}
+
 pub unsafe fn get_lib() -> &'static Lib {
     static LIB: std::sync::OnceLock<Lib> = std::sync::OnceLock::new();
-    LIB.get_or_init(|| Lib::new("/home/a/Software/aocl/5.1.0/gcc/lib/libflame.so").unwrap())
+    LIB.get_or_init(|| load_lib())
 }
 
 pub unsafe fn dgemm(
@@ -83,6 +164,41 @@ pub unsafe fn dgemm(
     (get_lib().dgemm)(transa, transb, m, n, k, alpha, a, lda, b, ldb, beta, c, ldc);
 }
 
+pub unsafe fn dgemv(
+    trans: *const c_char,
+    m: *const c_int,
+    n: *const c_int,
+    alpha: *const f64,
+    a: *const f64,
+    lda: *const c_int,
+    x: *const f64,
+    incx: *const c_int,
+    beta: *const f64,
+    y: *mut f64,
+    incy: *const c_int,
+) {
+    (get_lib().dgemv)(trans, m, n, alpha, a, lda, x, incx, beta, y, incy);
+}
+
+pub unsafe fn cblas_dgemm(
+    layout: c_int,
+    transa: c_int,
+    transb: c_int,
+    m: c_int,
+    n: c_int,
+    k: c_int,
+    alpha: f64,
+    a: *const f64,
+    lda: c_int,
+    b: *const f64,
+    ldb: c_int,
+    beta: f64,
+    c: *mut f64,
+    ldc: c_int,
+) {
+    (get_lib().cblas_dgemm)(layout, transa, transb, m, n, k, alpha, a, lda, b, ldb, beta, c, ldc);
+}
+
 pub unsafe fn dsyevd(
     jobz: *const c_char,
     uplo: *const c_char,
@@ -107,13 +223,65 @@ pub unsafe fn bli_thread_set_num_threads(n: c_int) {
     (get_lib().bli_thread_set_num_threads)(n);
 }
 
+pub unsafe fn bli_thread_set_ways(jc: i64, pc: i64, ic: i64, jr: i64, ir: i64) {
+    (get_lib().bli_thread_set_ways)(jc, pc, ic, jr, ir);
+}
+
+pub unsafe fn bli_thread_get_jc_nt() -> i64 {
+    (get_lib().bli_thread_get_jc_nt)()
+}
+
+pub unsafe fn bli_thread_get_pc_nt() -> i64 {
+    (get_lib().bli_thread_get_pc_nt)()
+}
+
+pub unsafe fn bli_thread_get_ic_nt() -> i64 {
+    (get_lib().bli_thread_get_ic_nt)()
+}
+
+pub unsafe fn bli_thread_get_jr_nt() -> i64 {
+    (get_lib().bli_thread_get_jr_nt)()
+}
+
+pub unsafe fn bli_thread_get_ir_nt() -> i64 {
+    (get_lib().bli_thread_get_ir_nt)()
+}
+
+pub unsafe fn omp_get_max_threads() -> c_int {
+    (get_lib().omp_get_max_threads)()
+}
+
+pub unsafe fn omp_set_num_threads(n: c_int) {
+    (get_lib().omp_set_num_threads)(n);
+}
+
+/// Applies the thread controller named in mode strings; `none` leaves the library untouched.
+fn set_num_threads(controller: &str, n: c_int) {
+    match controller {
+        "none" => (),
+        "set" => unsafe { bli_thread_set_num_threads(n) },
+        "gomp-set" => unsafe { omp_set_num_threads(n) },
+        _ => panic!("Unknown controller: {controller}"),
+    }
+}
+
+/// Reads back the thread count that the named controller acts on.
+fn get_num_threads(controller: &str) -> c_int {
+    match controller {
+        "none" | "set" => unsafe { bli_thread_get_num_threads() },
+        "gomp-set" => unsafe { omp_get_max_threads() },
+        _ => panic!("Unknown controller: {controller}"),
+    }
+}
+
+fn gen_mat() -> Vec<f64> {
+    (0..2048 * 2048).map(|x| x as f64 / 2048.0).collect()
+}
+
 fn gen_vecs() -> [Vec<Mutex<Vec<f64>>>; 3] {
-    let vec_a: Vec<Mutex<Vec<f64>>> =
-        (0..16).map(|_| Mutex::new((0..2048 * 2048).map(|x| x as f64 / 2048.0).collect())).collect();
-    let vec_b: Vec<Mutex<Vec<f64>>> =
-        (0..16).map(|_| Mutex::new((0..2048 * 2048).map(|x| x as f64 / 2048.0).collect())).collect();
-    let vec_c: Vec<Mutex<Vec<f64>>> =
-        (0..16).map(|_| Mutex::new((0..2048 * 2048).map(|x| x as f64 / 2048.0).collect())).collect();
+    let vec_a: Vec<Mutex<Vec<f64>>> = (0..16).map(|_| Mutex::new(gen_mat())).collect();
+    let vec_b: Vec<Mutex<Vec<f64>>> = (0..16).map(|_| Mutex::new(gen_mat())).collect();
+    let vec_c: Vec<Mutex<Vec<f64>>> = (0..16).map(|_| Mutex::new(gen_mat())).collect();
     [vec_a, vec_b, vec_c]
 }
 
@@ -122,23 +290,37 @@ fn run_blas(a: &[f64], b: &[f64], c: &mut [f64]) {
     let mut stat_p = perf_monitor::cpu::ProcessStat::cur().unwrap();
     let time = std::time::Instant::now();
     unsafe {
-        let t_char = b"T";
-        let n_char = b"N";
-        dgemm(
-            t_char.as_ptr() as *mut c_char,
-            n_char.as_ptr() as *mut c_char,
-            &n,
-            &n,
-            &n,
-            &3.0,
-            a.as_ptr(),
-            &n,
-            b.as_ptr(),
-            &n,
-            &0.0,
-            c.as_mut_ptr(),
-            &n,
-        );
+        match INTERFACE.get().map_or("fortran", String::as_str) {
+            "fortran" => {
+                let t_char = b"T";
+                let n_char = b"N";
+                dgemm(
+                    t_char.as_ptr() as *mut c_char,
+                    n_char.as_ptr() as *mut c_char,
+                    &n,
+                    &n,
+                    &n,
+                    &3.0,
+                    a.as_ptr(),
+                    &n,
+                    b.as_ptr(),
+                    &n,
+                    &0.0,
+                    c.as_mut_ptr(),
+                    &n,
+                );
+            },
+            "cblas-col" => {
+                let (a, b, c) = (a.as_ptr(), b.as_ptr(), c.as_mut_ptr());
+                cblas_dgemm(CBLAS_COL_MAJOR, CBLAS_TRANS, CBLAS_NO_TRANS, n, n, n, 3.0, a, n, b, n, 0.0, c, n);
+            },
+            "cblas-row" => {
+                // row-major view of column-major data is the transpose: C^T = B^T A gives the same memory as C = A^T B
+                let (a, b, c) = (a.as_ptr(), b.as_ptr(), c.as_mut_ptr());
+                cblas_dgemm(CBLAS_ROW_MAJOR, CBLAS_NO_TRANS, CBLAS_TRANS, n, n, n, 3.0, b, n, a, n, 0.0, c, n);
+            },
+            interface => panic!("Unknown interface: {interface}"),
+        }
     }
     let elapsed = time.elapsed();
     let usage_p = stat_p.cpu().unwrap() * 100.0;
@@ -146,19 +328,19 @@ fn run_blas(a: &[f64], b: &[f64], c: &mut [f64]) {
     println!("[CPU] rayon thread id {thread_id:2}, wall time: {elapsed:8.2?}, process usage: {usage_p:.2}%");
 }
 
-fn run_lapack(a: &mut [f64]) {
+fn run_lapack(a: &mut [f64]) -> Result<Vec<f64>, i32> {
     let n = 2048;
     let mut stat_p = perf_monitor::cpu::ProcessStat::cur().unwrap();
+    let mut w: Vec<f64> = vec![0.0; n];
+    let mut info: i32 = 0;
     let time = std::time::Instant::now();
     unsafe {
         let jobz = b"V";
         let uplo = b"U";
-        let mut w: Vec<f64> = vec![0.0; n];
         let lwork = 2 * (2 * n * n + 6 * n + 1);
         let liwork = 2 * (5 * n + 3);
         let mut work: Vec<f64> = vec![0.0; lwork];
         let mut iwork: Vec<i32> = vec![0; liwork];
-        let mut info: i32 = 0;
 
         dsyevd(
             jobz.as_ptr() as *const c_char,
@@ -178,12 +360,140 @@ fn run_lapack(a: &mut [f64]) {
     let usage_p = stat_p.cpu().unwrap() * 100.0;
     let thread_id = rayon::current_thread_index().unwrap_or(0);
     println!("[LAPACK] rayon thread id {thread_id:2}, wall time: {elapsed:8.2?}, process usage: {usage_p:.2}%");
+    if info == 0 {
+        Ok(w)
+    } else {
+        Err(info)
+    }
+}
+
+fn dot(x: &[f64], y: &[f64]) -> f64 {
+    let mut acc = [0.0; 4];
+    let mut x4 = x.chunks_exact(4);
+    let mut y4 = y.chunks_exact(4);
+    for (xs, ys) in (&mut x4).zip(&mut y4) {
+        acc.iter_mut().zip(xs).zip(ys).for_each(|((acc, x), y)| *acc += x * y);
+    }
+    let tail: f64 = x4.remainder().iter().zip(y4.remainder()).map(|(x, y)| x * y).sum();
+    acc.iter().sum::<f64>() + tail
+}
+
+/// Pure-Rust reference of `a^T * b` for column-major `n x n` matrices, blocked over columns and `k`.
+fn ref_gemm_tn(a: &[f64], b: &[f64], n: usize) -> Vec<f64> {
+    const NB: usize = 64;
+    const KB: usize = 256;
+    let mut c = vec![0.0; n * n];
+    c.par_chunks_mut(n * NB).enumerate().for_each(|(jb, c_blk)| {
+        for kb in (0..n).step_by(KB) {
+            let ke = (kb + KB).min(n);
+            for ib in (0..n).step_by(NB) {
+                for (jj, c_col) in c_blk.chunks_mut(n).enumerate() {
+                    let j = jb * NB + jj;
+                    let b_col = &b[j * n + kb..j * n + ke];
+                    for i in ib..(ib + NB).min(n) {
+                        c_col[i] += dot(&a[i * n + kb..i * n + ke], b_col);
+                    }
+                }
+            }
+        }
+    });
+    c
+}
+
+fn verify_blas(vec_c: &[Mutex<Vec<f64>>]) {
+    // all iterations multiply the same `gen_mat` inputs, so one reference serves every `c` of every scenario
+    static C_REF: std::sync::OnceLock<Vec<f64>> = std::sync::OnceLock::new();
+    let c_ref = C_REF.get_or_init(|| {
+        let a = gen_mat();
+        ref_gemm_tn(&a, &a, 2048).into_iter().map(|x| 3.0 * x).collect()
+    });
+    let c_max = c_ref.iter().fold(0.0_f64, |acc, x| acc.max(x.abs()));
+    for (i, c) in vec_c.iter().enumerate() {
+        let c = c.lock().unwrap();
+        let err = c.iter().zip(c_ref).fold(0.0_f64, |acc, (x, y)| acc.max((x - y).abs())) / c_max;
+        let status = if err < 1e-10 { "ok" } else { "FAILED" };
+        println!("[Verify] iter {i:2} dgemm max relative error: {err:.2e}, {status}");
+    }
+}
+
+fn norm_1(a: &[f64], n: usize) -> f64 {
+    a.chunks(n).map(|col| col.iter().map(|x| x.abs()).sum::<f64>()).fold(0.0, f64::max)
+}
+
+fn verify_lapack(vec_a: &[Mutex<Vec<f64>>], vec_w: &[Mutex<Result<Vec<f64>, i32>>]) {
+    // dsyevd only reads the upper triangle, so the reference works on its symmetric expansion
+    let n = 2048;
+    let a0 = gen_mat();
+    let a_sym: Vec<f64> = (0..n * n).map(|x| (x % n, x / n)).map(|(r, c)| a0[r.min(c) + r.max(c) * n]).collect();
+    let a_norm = norm_1(&a_sym, n);
+    for (i, (v, w)) in vec_a.iter().zip(vec_w).enumerate() {
+        let w = w.lock().unwrap();
+        let w = match w.as_ref() {
+            Ok(w) => w,
+            Err(info) => {
+                println!("[Verify] iter {i:2} dsyevd info: {info}, FAILED");
+                continue;
+            },
+        };
+        let v = v.lock().unwrap();
+
+        // A V - V Λ, with A V computed as A^T V since A is symmetric
+        let mut res = ref_gemm_tn(&a_sym, &v, n);
+        res.par_chunks_mut(n).zip(v.par_chunks(n)).zip(w.par_iter()).for_each(|((r, v), w)| {
+            r.iter_mut().zip(v).for_each(|(r, v)| *r -= w * v);
+        });
+        let res = norm_1(&res, n) / (n as f64 * a_norm * f64::EPSILON);
+
+        // V^T V - I
+        let mut orth = ref_gemm_tn(&v, &v, n);
+        (0..n).for_each(|j| orth[j * n + j] -= 1.0);
+        let orth = norm_1(&orth, n) / (n as f64 * f64::EPSILON);
+
+        // same scaling and threshold as the LAPACK test suite (dsyt21)
+        let status = if res < 30.0 && orth < 30.0 { "ok" } else { "FAILED" };
+        println!("[Verify] iter {i:2} dsyevd residual: {res:.2e}, orthogonality: {orth:.2e}, {status}");
+    }
+}
+
+/// Prints the wall time of a 16-iteration region, and for dgemm its throughput.
+fn report_region(routine: &str, elapsed: std::time::Duration) {
+    println!("[Process] {routine} region wall time: {elapsed:.2?}");
+    if routine == "dgemm" {
+        let rate = 16.0 * 2.0 * 2048.0_f64.powi(3) / 1e9 / elapsed.as_secs_f64();
+        println!("[Process] region throughput: {rate:7.2} GFLOP/s");
+    }
+}
+
+fn test_outer_set() {
+    println!("=== Outer, set ===");
+
+    let [vec_a, vec_b, vec_c] = gen_vecs();
+    unsafe { bli_thread_set_num_threads(1) };
+    let time = std::time::Instant::now();
+    (0..16).into_par_iter().for_each(|i| {
+        let thread_id = rayon::current_thread_index().unwrap_or(0);
+        println!("[Thread] iter {i:2} start, rayon thread id: {thread_id:2}");
+
+        let num_threads = unsafe { bli_thread_get_num_threads() };
+        println!("[Thread] iter {i:2} bli_thread_get_num_threads: {num_threads}");
+
+        let a = vec_a[i].lock().unwrap();
+        let b = vec_b[i].lock().unwrap();
+        let mut c = vec_c[i].lock().unwrap();
+        run_blas(&a, &b, &mut c);
+    });
+    let elapsed = time.elapsed();
+    let num_threads = unsafe { bli_thread_get_num_threads() };
+    println!("[Process] threads after iteration: {num_threads}");
+    report_region("dgemm", elapsed);
+    verify_blas(&vec_c);
 }
 
 fn test_inner_set() {
     println!("=== Inner, set ===");
 
     let [vec_a, vec_b, vec_c] = gen_vecs();
+    let time = std::time::Instant::now();
     (0..16).into_par_iter().for_each(|i| {
         unsafe { bli_thread_set_num_threads(1) };
 
@@ -198,14 +508,19 @@ fn test_inner_set() {
         let mut c = vec_c[i].lock().unwrap();
         run_blas(&a, &b, &mut c);
     });
+    let elapsed = time.elapsed();
     let num_threads = unsafe { bli_thread_get_num_threads() };
     println!("[Process] threads after iteration: {num_threads}");
+    report_region("dgemm", elapsed);
+    verify_blas(&vec_c);
 }
 
 fn test_inner_set_lapack() {
     println!("=== Inner, set_local ===");
 
     let [vec_a, _, _] = gen_vecs();
+    let vec_w: Vec<Mutex<Result<Vec<f64>, i32>>> = (0..16).map(|_| Mutex::new(Err(0))).collect();
+    let time = std::time::Instant::now();
     (0..16).into_par_iter().for_each(|i| {
         unsafe { bli_thread_set_num_threads(1) };
 
@@ -216,23 +531,971 @@ fn test_inner_set_lapack() {
         println!("[Thread] iter {i:2} bli_thread_get_num_threads: {num_threads}");
 
         let mut a = vec_a[i].lock().unwrap();
-        run_lapack(&mut a);
+        *vec_w[i].lock().unwrap() = run_lapack(&mut a);
     });
+    let elapsed = time.elapsed();
     let num_threads = unsafe { bli_thread_get_num_threads() };
     println!("[Process] threads after iteration: {num_threads}");
+    report_region("dsyevd", elapsed);
+    verify_lapack(&vec_a, &vec_w);
+}
+
+fn test_outer_gomp_set() {
+    println!("=== Outer, GOMP set ===");
+
+    let [vec_a, vec_b, vec_c] = gen_vecs();
+    unsafe { omp_set_num_threads(1) };
+    let time = std::time::Instant::now();
+    (0..16).into_par_iter().for_each(|i| {
+        let thread_id = rayon::current_thread_index().unwrap_or(0);
+        println!("[Thread] iter {i:2} start, rayon thread id: {thread_id:2}");
+
+        let num_threads = unsafe { omp_get_max_threads() };
+        println!("[Thread] iter {i:2} omp_get_max_threads: {num_threads}");
+
+        let a = vec_a[i].lock().unwrap();
+        let b = vec_b[i].lock().unwrap();
+        let mut c = vec_c[i].lock().unwrap();
+        run_blas(&a, &b, &mut c);
+    });
+    let elapsed = time.elapsed();
+    let num_threads = unsafe { omp_get_max_threads() };
+    println!("[Process] threads after iteration: {num_threads}");
+    report_region("dgemm", elapsed);
+    verify_blas(&vec_c);
+}
+
+fn test_inner_gomp_set() {
+    println!("=== Inner, GOMP set ===");
+
+    let [vec_a, vec_b, vec_c] = gen_vecs();
+    let time = std::time::Instant::now();
+    (0..16).into_par_iter().for_each(|i| {
+        unsafe { omp_set_num_threads(1) };
+
+        let thread_id = rayon::current_thread_index().unwrap_or(0);
+        println!("[Thread] iter {i:2} start, rayon thread id: {thread_id:2}");
+
+        let num_threads = unsafe { omp_get_max_threads() };
+        println!("[Thread] iter {i:2} omp_get_max_threads: {num_threads}");
+
+        let a = vec_a[i].lock().unwrap();
+        let b = vec_b[i].lock().unwrap();
+        let mut c = vec_c[i].lock().unwrap();
+        run_blas(&a, &b, &mut c);
+    });
+    let elapsed = time.elapsed();
+    let num_threads = unsafe { omp_get_max_threads() };
+    println!("[Process] threads after iteration: {num_threads}");
+    report_region("dgemm", elapsed);
+    verify_blas(&vec_c);
+}
+
+fn test_inner_gomp_set_lapack() {
+    println!("=== Inner, GOMP set lapack ===");
+
+    let [vec_a, _, _] = gen_vecs();
+    let vec_w: Vec<Mutex<Result<Vec<f64>, i32>>> = (0..16).map(|_| Mutex::new(Err(0))).collect();
+    let time = std::time::Instant::now();
+    (0..16).into_par_iter().for_each(|i| {
+        unsafe { omp_set_num_threads(1) };
+
+        let thread_id = rayon::current_thread_index().unwrap_or(0);
+        println!("[Thread] iter {i:2} start, rayon thread id: {thread_id:2}");
+
+        let num_threads = unsafe { omp_get_max_threads() };
+        println!("[Thread] iter {i:2} omp_get_max_threads: {num_threads}");
+
+        let mut a = vec_a[i].lock().unwrap();
+        *vec_w[i].lock().unwrap() = run_lapack(&mut a);
+    });
+    let elapsed = time.elapsed();
+    let num_threads = unsafe { omp_get_max_threads() };
+    println!("[Process] threads after iteration: {num_threads}");
+    report_region("dsyevd", elapsed);
+    verify_lapack(&vec_a, &vec_w);
+}
+
+/// CPU ticks (utime + stime) of every thread in this process, keyed by tid.
+fn thread_ticks() -> HashMap<u64, u64> {
+    std::fs::read_dir("/proc/self/task")
+        .unwrap()
+        .filter_map(|entry| {
+            let tid: u64 = entry.ok()?.file_name().to_str()?.parse().ok()?;
+            let stat = std::fs::read_to_string(format!("/proc/self/task/{tid}/stat")).ok()?;
+            // fields after the parenthesized comm start from field 3 (state); utime and stime are fields 14 and 15
+            let fields: Vec<&str> = stat.rsplit_once(')')?.1.split_whitespace().collect();
+            Some((tid, fields[11].parse::<u64>().ok()? + fields[12].parse::<u64>().ok()?))
+        })
+        .collect()
+}
+
+/// Repeats `call` for at least 200 ms, and counts threads alive and threads that consumed CPU meanwhile.
+fn census(call: &mut dyn FnMut()) -> (usize, usize, std::time::Duration, f64) {
+    let ticks_before = thread_ticks();
+    let mut stat_p = perf_monitor::cpu::ProcessStat::cur().unwrap();
+    let time = std::time::Instant::now();
+    let mut reps = 0;
+    while time.elapsed() < std::time::Duration::from_millis(200) {
+        call();
+        reps += 1;
+    }
+    let elapsed = time.elapsed() / reps;
+    let usage_p = stat_p.cpu().unwrap() * 100.0;
+    let ticks_after = thread_ticks();
+    let num_active = ticks_after.iter().filter(|(tid, &t)| t > ticks_before.get(tid).copied().unwrap_or(0)).count();
+    (ticks_after.len(), num_active, elapsed, usage_p)
+}
+
+fn test_sweep(routine: &str) {
+    println!("=== Sweep, {routine} ===");
+
+    // geometric sizes 8, 11, 16, 23, ..., 2048 (ratio sqrt(2)), all called from the main thread
+    let sizes = (0..=16).map(|k| (8.0 * 2.0_f64.powf(k as f64 / 2.0)).round() as usize);
+    let mut threaded_from = None;
+    for n in sizes {
+        let mut call = match routine {
+            "dgemm" => sweep_dgemm(n),
+            "dgemv" => sweep_dgemv(n),
+            "dsyevd" => sweep_dsyevd(n),
+            _ => panic!("Unknown routine: {routine}"),
+        };
+        let (num_threads, num_active, elapsed, usage_p) = census(&mut call);
+        println!(
+            "[Sweep] {routine} n = {n:4}, threads alive: {num_threads:2}, threads active: {num_active:2}, time per call: {elapsed:10.2?}, process usage: {usage_p:.2}%"
+        );
+        if threaded_from.is_none() && num_active > 1 {
+            threaded_from = Some(n);
+        }
+    }
+    match threaded_from {
+        Some(n) => println!("[Sweep] {routine} goes multi-threaded from n = {n}"),
+        None => println!("[Sweep] {routine} stays single-threaded up to n = 2048"),
+    }
+}
+
+fn sweep_dgemm(n: usize) -> Box<dyn FnMut()> {
+    let a: Vec<f64> = (0..n * n).map(|x| x as f64 / n as f64).collect();
+    let b = a.clone();
+    let mut c = vec![0.0; n * n];
+    let n = n as c_int;
+    Box::new(move || unsafe {
+        let t_char = b"T";
+        let n_char = b"N";
+        dgemm(
+            t_char.as_ptr() as *mut c_char,
+            n_char.as_ptr() as *mut c_char,
+            &n,
+            &n,
+            &n,
+            &3.0,
+            a.as_ptr(),
+            &n,
+            b.as_ptr(),
+            &n,
+            &0.0,
+            c.as_mut_ptr(),
+            &n,
+        );
+    })
+}
+
+fn sweep_dgemv(n: usize) -> Box<dyn FnMut()> {
+    let a: Vec<f64> = (0..n * n).map(|x| x as f64 / n as f64).collect();
+    let x: Vec<f64> = (0..n).map(|x| x as f64 / n as f64).collect();
+    let mut y = vec![0.0; n];
+    let n = n as c_int;
+    Box::new(move || unsafe {
+        let n_char = b"N";
+        dgemv(n_char.as_ptr() as *const c_char, &n, &n, &3.0, a.as_ptr(), &n, x.as_ptr(), &1, &0.0, y.as_mut_ptr(), &1);
+    })
+}
+
+fn sweep_dsyevd(n: usize) -> Box<dyn FnMut()> {
+    let a0: Vec<f64> = (0..n * n).map(|x| x as f64 / n as f64).collect();
+    let mut a = a0.clone();
+    let mut w: Vec<f64> = vec![0.0; n];
+    let lwork = 2 * (2 * n * n + 6 * n + 1);
+    let liwork = 2 * (5 * n + 3);
+    let mut work: Vec<f64> = vec![0.0; lwork];
+    let mut iwork: Vec<i32> = vec![0; liwork];
+    Box::new(move || unsafe {
+        let jobz = b"V";
+        let uplo = b"U";
+        let mut info: i32 = 0;
+        // dsyevd overwrites `a` with eigenvectors, so every call starts from a fresh copy
+        a.copy_from_slice(&a0);
+        dsyevd(
+            jobz.as_ptr() as *const c_char,
+            uplo.as_ptr() as *const c_char,
+            &(n as _),
+            a.as_mut_ptr(),
+            &(n as _),
+            w.as_mut_ptr(),
+            work.as_mut_ptr(),
+            &mut (lwork as _),
+            iwork.as_mut_ptr(),
+            &mut (liwork as _),
+            &mut info,
+        );
+    })
+}
+
+/// tid of the calling thread, read from the `/proc/thread-self` link.
+fn gettid() -> u64 {
+    let link = std::fs::read_link("/proc/thread-self").unwrap();
+    link.file_name().unwrap().to_str().unwrap().parse().unwrap()
+}
+
+/// Threads other than rayon workers and the main thread that consumed CPU between two `thread_ticks`, with their ticks.
+fn foreign_ticks(before: &HashMap<u64, u64>, after: &HashMap<u64, u64>, own: &HashSet<u64>) -> (usize, u64) {
+    let deltas = after
+        .iter()
+        .filter(|(tid, _)| !own.contains(tid))
+        .map(|(tid, &t)| t.saturating_sub(before.get(tid).copied().unwrap_or(0)));
+    deltas.filter(|&d| d > 0).fold((0, 0), |(num, sum), d| (num + 1, sum + d))
+}
+
+/// CPU-bound, rayon-native element-wise work that reads `c` and leaves it untouched.
+fn run_rust_kernel(c: &[f64]) -> f64 {
+    (0..8).map(|k| c.par_iter().map(|x| (x.abs() + k as f64).sqrt().sin()).sum::<f64>()).sum()
+}
+
+fn test_mixed(controller: &str) {
+    println!("=== Mixed, {controller} ===");
+
+    // every thread that is not a rayon worker or the main thread belongs to the BLAS runtime
+    let own: HashSet<u64> = rayon::broadcast(|_| gettid()).into_iter().chain([gettid()]).collect();
+    let num_alive = thread_ticks().len();
+
+    // BLAS, Rust and LAPACK run as separate parallel phases; the join after the BLAS phase is a barrier, so no BLAS
+    // call is in flight while the Rust kernels sample the BLAS threads
+    let [vec_a, vec_b, vec_c] = gen_vecs();
+    (0..16).into_par_iter().for_each(|i| {
+        set_num_threads(controller, 1);
+
+        let thread_id = rayon::current_thread_index().unwrap_or(0);
+        println!("[Thread] iter {i:2} start, rayon thread id: {thread_id:2}");
+
+        let num_threads = get_num_threads(controller);
+        println!("[Thread] iter {i:2} threads ({controller}): {num_threads}");
+
+        let a = vec_a[i].lock().unwrap();
+        let b = vec_b[i].lock().unwrap();
+        let mut c = vec_c[i].lock().unwrap();
+        run_blas(&a, &b, &mut c);
+    });
+
+    let max_foreign = std::sync::atomic::AtomicUsize::new(0);
+    (0..16).into_par_iter().for_each(|i| {
+        let thread_id = rayon::current_thread_index().unwrap_or(0);
+        let c = vec_c[i].lock().unwrap();
+        let ticks_before = thread_ticks();
+        let time = std::time::Instant::now();
+        let sum = run_rust_kernel(&c);
+        let elapsed = time.elapsed();
+        let (num_foreign, ticks) = foreign_ticks(&ticks_before, &thread_ticks(), &own);
+        max_foreign.fetch_max(num_foreign, std::sync::atomic::Ordering::Relaxed);
+        println!(
+            "[Rust] rayon thread id {thread_id:2}, wall time: {elapsed:8.2?}, BLAS threads active: {num_foreign:2}, BLAS thread ticks: {ticks:4}, checksum: {sum:.3e}"
+        );
+    });
+
+    let vec_w: Vec<Mutex<Result<Vec<f64>, i32>>> = (0..16).map(|_| Mutex::new(Err(0))).collect();
+    (0..16).into_par_iter().for_each(|i| {
+        set_num_threads(controller, 1);
+        let mut a = vec_a[i].lock().unwrap();
+        *vec_w[i].lock().unwrap() = run_lapack(&mut a);
+    });
+    let num_threads = get_num_threads(controller);
+    println!("[Process] threads after iteration: {num_threads}");
+
+    // after the region, the BLAS runtime has nothing to do; any CPU it burns now is spinning
+    let ticks_before = thread_ticks();
+    std::thread::sleep(std::time::Duration::from_millis(500));
+    let ticks_after = thread_ticks();
+    let (num_idle, idle_ticks) = foreign_ticks(&ticks_before, &ticks_after, &own);
+    let num_foreign = ticks_after.keys().filter(|tid| !own.contains(tid)).count();
+    let max_foreign = max_foreign.into_inner();
+    println!("[Process] threads alive before region: {num_alive}, after region: {}", ticks_after.len());
+    println!("[Process] BLAS threads alive: {num_foreign}, max active during Rust kernels: {max_foreign}");
+    println!("[Process] BLAS threads active in 500 ms idle window: {num_idle}, ticks: {idle_ticks}");
+    let verdict = match (max_foreign, num_idle) {
+        (_, 1..) => "spin",
+        (1.., 0) => "linger",
+        (0, 0) => "quiet",
+    };
+    println!("[Verdict] BLAS thread pool between calls: {verdict}");
+
+    verify_blas(&vec_c);
+    verify_lapack(&vec_a, &vec_w);
+}
+
+fn test_steal(controller: &str) {
+    println!("=== Work stealing, {controller} ===");
+
+    let num_default = get_num_threads(controller);
+    println!("[Process] threads before iteration: {num_default}");
+
+    // only every 4th iteration sets threads; the others are unrelated tasks that may land on the same worker
+    let [vec_a, vec_b, vec_c] = gen_vecs();
+    let order = std::sync::atomic::AtomicUsize::new(0);
+    let records = Mutex::new(vec![]);
+    (0..16).into_par_iter().for_each(|i| {
+        let seq = order.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        let thread_id = rayon::current_thread_index().unwrap_or(0);
+        let sets = i % 4 == 0;
+        println!("[Thread] iter {i:2} start, rayon thread id: {thread_id:2}, order: {seq:2}, sets threads: {sets}");
+
+        if sets {
+            set_num_threads(controller, 1);
+        }
+        let num_threads = get_num_threads(controller);
+        println!("[Thread] iter {i:2} threads ({controller}): {num_threads}");
+
+        let a = vec_a[i].lock().unwrap();
+        let b = vec_b[i].lock().unwrap();
+        let mut c = vec_c[i].lock().unwrap();
+        run_blas(&a, &b, &mut c);
+        records.lock().unwrap().push((seq, i, thread_id, sets, num_threads));
+    });
+    let num_threads = get_num_threads(controller);
+    println!("[Process] threads after iteration: {num_threads}");
+
+    let mut records = records.into_inner().unwrap();
+    records.sort();
+    let mut setter_workers = HashSet::new();
+    let (mut num_changed, mut num_inherited) = (0, 0);
+    for &(seq, i, thread_id, sets, num_threads) in &records {
+        let after_setter = setter_workers.contains(&thread_id);
+        if sets {
+            setter_workers.insert(thread_id);
+        } else if num_threads != num_default {
+            num_changed += 1;
+            num_inherited += after_setter as usize;
+        }
+        let status = match (sets, num_threads != num_default, after_setter) {
+            (true, ..) => "setter",
+            (false, true, true) => "inherited from earlier setter on this worker",
+            (false, true, false) => "changed without a setter on this worker",
+            (false, false, _) => "default",
+        };
+        println!(
+            "[Steal] order {seq:2}, iter {i:2}, rayon thread id {thread_id:2}, threads: {num_threads:2}, {status}"
+        );
+    }
+    let num_unrelated = records.iter().filter(|r| !r.3).count();
+    println!(
+        "[Verdict] {num_changed} of {num_unrelated} non-setting iterations saw a changed thread count, {num_inherited} on a worker that ran a setter before"
+    );
+
+    verify_blas(&vec_c);
+}
+
+/// One iteration of the controller scenarios, run on a plain (non-rayon) thread.
+fn run_iteration(worker: usize, i: usize, place: &str, controller: &str, vecs: &[Vec<Mutex<Vec<f64>>>; 3]) {
+    if place == "inner" {
+        set_num_threads(controller, 1);
+    }
+
+    println!("[Thread] iter {i:2} start, worker id: {worker:2}, tid: {}", gettid());
+
+    let num_threads = get_num_threads(controller);
+    println!("[Thread] iter {i:2} threads ({controller}): {num_threads}");
+
+    let [vec_a, vec_b, vec_c] = vecs;
+    let a = vec_a[i].lock().unwrap();
+    let b = vec_b[i].lock().unwrap();
+    let mut c = vec_c[i].lock().unwrap();
+    run_blas(&a, &b, &mut c);
+}
+
+fn test_threads(kind: &str, place: &str, controller: &str) {
+    println!("=== {kind} threads, {place}, {controller} ===");
+
+    // same number of workers as the rayon pool, so CPU usage compares with the rayon scenarios
+    let num_workers = rayon::current_num_threads();
+    let vecs = std::sync::Arc::new(gen_vecs());
+    match place {
+        "outer" => set_num_threads(controller, 1),
+        "inner" => (),
+        _ => panic!("Unknown place: {place}"),
+    }
+
+    match kind {
+        "scoped" => {
+            // fresh threads per scenario, pulling iterations from a shared counter
+            let next = std::sync::atomic::AtomicUsize::new(0);
+            std::thread::scope(|s| {
+                for worker in 0..num_workers {
+                    let (next, vecs) = (&next, &vecs);
+                    s.spawn(move || loop {
+                        let i = next.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                        if i >= 16 {
+                            break;
+                        }
+                        run_iteration(worker, i, place, controller, vecs);
+                    });
+                }
+            });
+        },
+        "persistent" => {
+            // long-lived workers fed over channels, like a service's own thread pool
+            let workers = (0..num_workers)
+                .map(|worker| {
+                    let (tx, rx) = std::sync::mpsc::channel::<usize>();
+                    let (vecs, place, controller) = (vecs.clone(), place.to_string(), controller.to_string());
+                    let handle = std::thread::spawn(move || {
+                        for i in rx {
+                            run_iteration(worker, i, &place, &controller, &vecs);
+                        }
+                    });
+                    (tx, handle)
+                })
+                .collect::<Vec<_>>();
+            (0..16).for_each(|i| workers[i % num_workers].0.send(i).unwrap());
+            workers.into_iter().for_each(|(tx, handle)| {
+                drop(tx);
+                handle.join().unwrap();
+            });
+        },
+        _ => panic!("Unknown thread kind: {kind}"),
+    }
+    let num_threads = get_num_threads(controller);
+    println!("[Process] threads after iteration: {num_threads}");
+
+    verify_blas(&vecs[2]);
+}
+
+/// Runs the 16 dgemm iterations on `pool` (the global pool if `None`), applying the controller in each iteration if
+/// `set`; returns the number of iterations that observed a thread count other than `num_default`.
+fn run_on_pool(
+    pool: Option<&rayon::ThreadPool>,
+    label: &str,
+    set: bool,
+    controller: &str,
+    num_default: c_int,
+    vecs: &[Vec<Mutex<Vec<f64>>>; 3],
+) -> usize {
+    let [vec_a, vec_b, vec_c] = vecs;
+    let region = || {
+        (0..16)
+            .into_par_iter()
+            .map(|i| {
+                if set {
+                    set_num_threads(controller, 1);
+                }
+                let thread_id = rayon::current_thread_index().unwrap_or(0);
+                let num_threads = get_num_threads(controller);
+                println!(
+                    "[Thread] {label} pool, iter {i:2}, rayon thread id: {thread_id:2}, tid: {}, threads ({controller}): {num_threads}",
+                    gettid()
+                );
+
+                let a = vec_a[i].lock().unwrap();
+                let b = vec_b[i].lock().unwrap();
+                let mut c = vec_c[i].lock().unwrap();
+                run_blas(&a, &b, &mut c);
+                (num_threads != num_default) as usize
+            })
+            .sum()
+    };
+    match pool {
+        Some(pool) => pool.install(region),
+        None => region(),
+    }
+}
+
+fn test_pool(case: &str, controller: &str) {
+    println!("=== Pool {case}, {controller} ===");
+
+    let num_default = get_num_threads(controller);
+    println!("[Process] threads before iteration: {num_default}");
+    let num_workers = rayon::current_num_threads();
+    let build_pool = || rayon::ThreadPoolBuilder::new().num_threads(num_workers).build().unwrap();
+    let vecs = gen_vecs();
+
+    let num_changed = match case {
+        "nested" => {
+            // a library's own pool, installed from inside our par_iter after our worker applied the controller
+            let second = build_pool();
+            let [vec_a, vec_b, vec_c] = &vecs;
+            (0..16)
+                .into_par_iter()
+                .map(|i| {
+                    set_num_threads(controller, 1);
+                    let outer_id = rayon::current_thread_index().unwrap_or(0);
+                    let outer_num = get_num_threads(controller);
+                    second.install(|| {
+                        let thread_id = rayon::current_thread_index().unwrap_or(0);
+                        let num_threads = get_num_threads(controller);
+                        println!(
+                            "[Thread] iter {i:2}, global rayon thread id: {outer_id:2}, threads: {outer_num}; second rayon thread id: {thread_id:2}, threads ({controller}): {num_threads}"
+                        );
+
+                        let a = vec_a[i].lock().unwrap();
+                        let b = vec_b[i].lock().unwrap();
+                        let mut c = vec_c[i].lock().unwrap();
+                        run_blas(&a, &b, &mut c);
+                        (num_threads != num_default) as usize
+                    })
+                })
+                .sum()
+        },
+        "sequential" => {
+            run_on_pool(None, "global", true, controller, num_default, &vecs);
+            let second = build_pool();
+            run_on_pool(Some(&second), "second", false, controller, num_default, &vecs)
+        },
+        "recreate" => {
+            let first = build_pool();
+            run_on_pool(Some(&first), "first", true, controller, num_default, &vecs);
+            drop(first);
+            let second = build_pool();
+            run_on_pool(Some(&second), "recreated", false, controller, num_default, &vecs)
+        },
+        _ => panic!("Unknown pool case: {case}"),
+    };
+    let num_threads = get_num_threads(controller);
+    println!("[Process] threads after iteration: {num_threads}");
+    println!("[Verdict] {num_changed} of 16 iterations in the non-setting pool saw a changed thread count");
+
+    verify_blas(&vecs[2]);
+}
+
+fn test_tokio(controller: &str) {
+    println!("=== Tokio spawn_blocking, {controller} ===");
+
+    let num_default = get_num_threads(controller);
+    println!("[Process] threads before iteration: {num_default}");
+
+    // blocking pool keeps tokio's default limit of 512 threads; async workers match the rayon pool size
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(rayon::current_num_threads())
+        .max_blocking_threads(512)
+        .build()
+        .unwrap();
+    let vecs = std::sync::Arc::new(gen_vecs());
+    let mut stat_p = perf_monitor::cpu::ProcessStat::cur().unwrap();
+    let time = std::time::Instant::now();
+    let blocking_tids = runtime.block_on(async {
+        let handles = (0..16)
+            .map(|i| {
+                let (vecs, controller) = (vecs.clone(), controller.to_string());
+                tokio::task::spawn_blocking(move || {
+                    set_num_threads(&controller, 1);
+
+                    let tid = gettid();
+                    println!("[Thread] iter {i:2} start, blocking thread tid: {tid}");
+
+                    let num_threads = get_num_threads(&controller);
+                    println!("[Thread] iter {i:2} threads ({controller}): {num_threads}");
+
+                    let [vec_a, vec_b, vec_c] = vecs.as_ref();
+                    let a = vec_a[i].lock().unwrap();
+                    let b = vec_b[i].lock().unwrap();
+                    let mut c = vec_c[i].lock().unwrap();
+                    run_blas(&a, &b, &mut c);
+                    tid
+                })
+            })
+            .collect::<Vec<_>>();
+        let mut tids = HashSet::new();
+        for handle in handles {
+            tids.insert(handle.await.unwrap());
+        }
+        tids
+    });
+    let elapsed = time.elapsed();
+    let usage_p = stat_p.cpu().unwrap() * 100.0;
+    let num_blocking = blocking_tids.len();
+    println!("[Process] 16 blocking tasks, wall time: {elapsed:8.2?}, process usage: {usage_p:.2}%");
+    println!(
+        "[Process] distinct blocking threads: {num_blocking}, effective control keeps usage within {}%",
+        num_blocking * 100
+    );
+
+    // idle blocking threads are reused by later tasks; these never apply the controller
+    let num_leaked = runtime.block_on(async {
+        let mut num_leaked = 0;
+        for i in 0..16 {
+            let owned = controller.to_string();
+            let (tid, num_threads) =
+                tokio::task::spawn_blocking(move || (gettid(), get_num_threads(&owned))).await.unwrap();
+            let reused = blocking_tids.contains(&tid);
+            println!("[Leak] task {i:2}, blocking thread tid: {tid}, reused: {reused}, threads ({controller}): {num_threads}");
+            num_leaked += (num_threads != num_default) as usize;
+        }
+        num_leaked
+    });
+    let num_threads = get_num_threads(controller);
+    println!("[Process] threads after iteration: {num_threads}");
+    println!("[Verdict] {num_leaked} of 16 later non-setting blocking tasks saw a changed thread count");
+
+    verify_blas(&vecs[2]);
+}
+
+/// dgemm on the calling thread with a thread census: wall time, active BLAS threads (outside `own`), all active threads.
+fn run_blas_census(a: &[f64], b: &[f64], c: &mut [f64], own: &HashSet<u64>) -> (std::time::Duration, usize, usize) {
+    let ticks_before = thread_ticks();
+    let time = std::time::Instant::now();
+    run_blas(a, b, c);
+    let elapsed = time.elapsed();
+    let ticks_after = thread_ticks();
+    let (num_foreign, _) = foreign_ticks(&ticks_before, &ticks_after, own);
+    let (num_all, _) = foreign_ticks(&ticks_before, &ticks_after, &HashSet::new());
+    (elapsed, num_foreign, num_all)
+}
+
+fn test_main_concurrent(controller: &str) {
+    println!("=== Main thread concurrent, {controller} ===");
+
+    let own: HashSet<u64> = rayon::broadcast(|_| gettid()).into_iter().chain([gettid()]).collect();
+    let num_cpus = std::thread::available_parallelism().unwrap().get();
+    let (a, b, mut c) = (gen_mat(), gen_mat(), gen_mat());
+
+    // baseline: the same full-thread call on the main thread, with nothing else running
+    let (elapsed, num_base, num_all) = run_blas_census(&a, &b, &mut c, &own);
+    println!("[Main] alone, wall time: {elapsed:8.2?}, BLAS threads active: {num_base:2}, threads active: {num_all:2}");
+
+    let [vec_a, vec_b, vec_c] = gen_vecs();
+    let started = std::sync::atomic::AtomicBool::new(false);
+    let (elapsed, num_foreign, num_all) = std::thread::scope(|s| {
+        // the rayon region is driven from a helper thread, so the main thread stays free for its own call
+        s.spawn(|| {
+            (0..16).into_par_iter().for_each(|i| {
+                set_num_threads(controller, 1);
+                started.store(true, std::sync::atomic::Ordering::SeqCst);
+
+                let thread_id = rayon::current_thread_index().unwrap_or(0);
+                println!("[Thread] iter {i:2} start, rayon thread id: {thread_id:2}");
+
+                let num_threads = get_num_threads(controller);
+                println!("[Thread] iter {i:2} threads ({controller}): {num_threads}");
+
+                let a = vec_a[i].lock().unwrap();
+                let b = vec_b[i].lock().unwrap();
+                let mut c = vec_c[i].lock().unwrap();
+                run_blas(&a, &b, &mut c);
+            });
+        });
+        while !started.load(std::sync::atomic::Ordering::SeqCst) {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        // let every rayon worker enter its dgemm before the main thread starts
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        let num_threads = get_num_threads(controller);
+        println!("[Main] threads ({controller}) seen by main thread: {num_threads}");
+        run_blas_census(&a, &b, &mut c, &own)
+    });
+    println!(
+        "[Main] concurrent, wall time: {elapsed:8.2?}, BLAS threads active: {num_foreign:2}, threads active: {num_all:2}, logical CPUs: {num_cpus}"
+    );
+    let num_threads = get_num_threads(controller);
+    println!("[Process] threads after iteration: {num_threads}");
+
+    let verdict = if num_all > num_cpus {
+        "oversubscribes the machine"
+    } else if num_foreign < num_base {
+        "throttled"
+    } else {
+        "gets all its threads"
+    };
+    println!("[Verdict] main-thread dgemm during the rayon region: {verdict}");
+
+    println!("[Main] verification of the main-thread dgemm:");
+    verify_blas(&[Mutex::new(c)]);
+    verify_blas(&vec_c);
+}
+
+/// Number of threads of a process, from `/proc/<pid>/task`.
+fn num_tasks(pid: &str) -> usize {
+    std::fs::read_dir(format!("/proc/{pid}/task")).map(|dir| dir.count()).unwrap_or(0)
+}
+
+fn test_fork() {
+    use std::io::Write;
+    println!("=== Fork after initialisation ===");
+
+    // initialise the backend and its thread pool in the parent; the result is the child's reference
+    let (a, b, mut c_ref) = (gen_mat(), gen_mat(), gen_mat());
+    run_blas(&a, &b, &mut c_ref);
+    let num_threads = get_num_threads("none");
+    println!("[Parent] threads (none): {num_threads}, process threads: {}", num_tasks("self"));
+
+    std::io::stdout().flush().unwrap();
+    let pid = unsafe { libc::fork() };
+    assert!(pid >= 0, "fork failed");
+    if pid == 0 {
+        // only this thread survives in the child; the rayon pool is gone, so nothing here may use it
+        let num_threads_child = get_num_threads("none");
+        println!("[Child] threads (none): {num_threads_child}, process threads: {}", num_tasks("self"));
+
+        let own = HashSet::from([gettid()]);
+        let mut c = gen_mat();
+        let ticks_before = thread_ticks();
+        let mut stat_p = perf_monitor::cpu::ProcessStat::cur().unwrap();
+        let time = std::time::Instant::now();
+        run_blas(&a, &b, &mut c);
+        let elapsed = time.elapsed();
+        let usage_p = stat_p.cpu().unwrap() * 100.0;
+        let (num_foreign, _) = foreign_ticks(&ticks_before, &thread_ticks(), &own);
+        let scale = c_ref.iter().fold(0.0_f64, |acc, x| acc.max(x.abs()));
+        let err = c.iter().zip(c_ref).fold(0.0_f64, |acc, (x, y)| acc.max((x - y).abs())) / scale;
+        println!(
+            "[Child] wall time: {elapsed:8.2?}, process usage: {usage_p:5.1} %, BLAS threads active: {num_foreign:2}, process threads: {}",
+            num_tasks("self")
+        );
+        println!("[Child] max relative deviation from the parent's result: {err:.3e}");
+
+        let verdict = if err > 1e-10 {
+            "wrong result".to_string()
+        } else if num_foreign == 0 && num_threads > 1 {
+            "silently single-threaded".to_string()
+        } else {
+            format!("works, {} threads", num_foreign + 1)
+        };
+        println!("[Verdict] dgemm after fork: {verdict}");
+        std::io::stdout().flush().unwrap();
+        unsafe { libc::_exit(0) };
+    }
+
+    match child_watch::wait_timeout(pid, std::time::Duration::from_secs(10), "[Parent]") {
+        child_watch::Exit::Code(code) => println!("[Parent] child exited with status {code}"),
+        child_watch::Exit::Signal(signal) => println!("[Verdict] dgemm after fork: crashes, signal {signal}"),
+        child_watch::Exit::Timeout => println!("[Verdict] dgemm after fork: hangs"),
+    }
+}
+
+/// Files mapped into the process, from `/proc/self/maps`.
+fn mapped_files() -> HashSet<String> {
+    let maps = std::fs::read_to_string("/proc/self/maps").unwrap();
+    maps.lines().filter_map(|l| l.split_whitespace().nth(5)).filter(|p| p.starts_with('/')).map(String::from).collect()
+}
+
+/// Child side of `reload`: load, run, `dlclose` and reload the backend twice without touching `get_lib`.
+fn test_reload_cycle() {
+    println!("=== Reload cycle ===");
+
+    let own: HashSet<u64> = rayon::broadcast(|_| gettid()).into_iter().chain([gettid()]).collect();
+    let num_base = num_tasks("self");
+    let maps_base = mapped_files();
+    println!("[Reload] before loading, process threads: {num_base}");
+
+    let (a, b) = (gen_mat(), gen_mat());
+    for round in 0..2 {
+        let lib = unsafe { load_lib() };
+        let mut maps_lib: Vec<String> = mapped_files().difference(&maps_base).cloned().collect();
+        maps_lib.sort();
+
+        let n = 2048;
+        let mut c = gen_mat();
+        let time = std::time::Instant::now();
+        unsafe {
+            (lib.dgemm)(
+                b"T".as_ptr() as *mut c_char,
+                b"N".as_ptr() as *mut c_char,
+                &n,
+                &n,
+                &n,
+                &3.0,
+                a.as_ptr(),
+                &n,
+                b.as_ptr(),
+                &n,
+                &0.0,
+                c.as_mut_ptr(),
+                &n,
+            )
+        };
+        let elapsed = time.elapsed();
+        println!(
+            "[Reload] round {round}, wall time: {elapsed:8.2?}, process threads: {}, files mapped by the backend: {}",
+            num_tasks("self"),
+            maps_lib.len()
+        );
+        verify_blas(&[Mutex::new(c)]);
+
+        drop(lib);
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        let ticks_before = thread_ticks();
+        std::thread::sleep(std::time::Duration::from_millis(500));
+        let (num_busy, _) = foreign_ticks(&ticks_before, &thread_ticks(), &own);
+        let num_leaked = num_tasks("self").saturating_sub(num_base);
+        let maps_now = mapped_files();
+        let still_mapped: Vec<&String> = maps_lib.iter().filter(|p| maps_now.contains(*p)).collect();
+        println!(
+            "[Reload] round {round}, after dlclose, leaked threads: {num_leaked}, busy: {num_busy}, still mapped: {}/{}",
+            still_mapped.len(),
+            maps_lib.len()
+        );
+        for path in still_mapped {
+            println!("[Reload]   still mapped: {path}");
+        }
+    }
+    println!("[Reload] returning from main, process threads: {}", num_tasks("self"));
+}
+
+fn test_reload() {
+    use std::io::Write;
+    println!("=== Unload and reload ===");
+
+    // the cycle runs in a fresh process, so that no `get_lib` handle pins the library and its exit is observable
+    std::io::stdout().flush().unwrap();
+    // `wait_timeout` reaps the child by pid, so the `Child` handle is not kept
+    let pid =
+        std::process::Command::new(std::env::current_exe().unwrap()).arg("reload-cycle").spawn().unwrap().id() as i32;
+    let verdict = match child_watch::wait_timeout(pid, std::time::Duration::from_secs(60), "[Parent]") {
+        child_watch::Exit::Code(0) => "clean exit".to_string(),
+        child_watch::Exit::Code(code) => format!("fails, exit code {code}"),
+        child_watch::Exit::Signal(signal) => format!("crashes, signal {signal}"),
+        child_watch::Exit::Timeout => "hangs".to_string(),
+    };
+    println!("[Verdict] unload and reload: {verdict}");
+}
+
+/// Loop-level ways as seen from the calling thread: `[jc, pc, ic, jr, ir]`.
+fn get_ways() -> [i64; 5] {
+    unsafe {
+        [
+            bli_thread_get_jc_nt(),
+            bli_thread_get_pc_nt(),
+            bli_thread_get_ic_nt(),
+            bli_thread_get_jr_nt(),
+            bli_thread_get_ir_nt(),
+        ]
+    }
+}
+
+fn test_ways(case: &str) {
+    println!("=== Inner, set ways ({case}) ===");
+
+    // `single`: every worker uses 1x1x1x1x1; `split`: the worker running iteration 0 uses a 2x2 (ic x jr) split
+    let ways_for = |i: usize| match case {
+        "single" => [1, 1, 1, 1, 1],
+        "split" if i == 0 => [1, 1, 2, 2, 1],
+        "split" => [1, 1, 1, 1, 1],
+        _ => panic!("Unknown ways case: {case}"),
+    };
+    let ways_before = get_ways();
+    println!("[Process] ways (jc, pc, ic, jr, ir) before iteration: {ways_before:?}");
+
+    let n = 2048.0_f64;
+    let gflop = 2.0 * n * n * n / 1e9;
+    let [vec_a, vec_b, vec_c] = gen_vecs();
+    let mut stat_p = perf_monitor::cpu::ProcessStat::cur().unwrap();
+    let time = std::time::Instant::now();
+    (0..16).into_par_iter().for_each(|i| {
+        let [jc, pc, ic, jr, ir] = ways_for(i);
+        unsafe { bli_thread_set_ways(jc, pc, ic, jr, ir) };
+
+        let thread_id = rayon::current_thread_index().unwrap_or(0);
+        println!("[Thread] iter {i:2} start, rayon thread id: {thread_id:2}");
+
+        let a = vec_a[i].lock().unwrap();
+        let b = vec_b[i].lock().unwrap();
+        let mut c = vec_c[i].lock().unwrap();
+        let ways = get_ways();
+        let time = std::time::Instant::now();
+        run_blas(&a, &b, &mut c);
+        let rate = gflop / time.elapsed().as_secs_f64();
+        println!("[Ways] iter {i:2} set: {:?}, observed: {ways:?}, throughput: {rate:7.2} GFLOP/s", ways_for(i));
+    });
+    let rate = 16.0 * gflop / time.elapsed().as_secs_f64();
+    let usage_p = stat_p.cpu().unwrap() * 100.0;
+    println!("[Process] region throughput: {rate:7.2} GFLOP/s, process usage: {usage_p:.2}%");
+
+    // the main thread never sets ways, so a change seen here came from a rayon worker
+    let ways_after = get_ways();
+    println!("[Process] ways (jc, pc, ic, jr, ir) after iteration: {ways_after:?}");
+    let scope = if ways_after == ways_before { "per-thread" } else { "global" };
+    println!("[Verdict] bli_thread_set_ways is {scope}");
+    verify_blas(&vec_c);
+}
+
+/// `AOCL_DYNAMIC` as seen by the library, which reads it once at BLIS initialisation (the first call); unset means
+/// enabled.
+fn aocl_dynamic() -> String {
+    std::env::var("AOCL_DYNAMIC").unwrap_or_else(|_| "unset (enabled)".to_string())
+}
+
+fn test_dynamic() {
+    println!("=== Dynamic, AOCL_DYNAMIC {} ===", aocl_dynamic());
+
+    // request every core from the main thread, so that any shortfall is AOCL's own decision
+    let num_cpus = std::thread::available_parallelism().unwrap().get();
+    unsafe { bli_thread_set_num_threads(num_cpus as c_int) };
+    let mut reduced_up_to = None;
+    for n in (6..=11).map(|k| 1 << k) {
+        let mut call = sweep_dgemm(n);
+        let (_, num_active, elapsed, usage_p) = census(&mut call);
+        println!(
+            "[Dynamic] dgemm n = {n:4}, threads requested: {num_cpus:2}, threads active: {num_active:2}, time per call: {elapsed:10.2?}, process usage: {usage_p:.2}%"
+        );
+        if num_active < num_cpus {
+            reduced_up_to = Some(n);
+        }
+    }
+    let num_threads = unsafe { bli_thread_get_num_threads() };
+    println!("[Process] bli_thread_get_num_threads after sweep: {num_threads}");
+    match reduced_up_to {
+        Some(n) => println!("[Verdict] AOCL uses fewer threads than requested, up to n = {n}"),
+        None => println!("[Verdict] AOCL uses the requested {num_cpus} threads at every size"),
+    }
 }
 
 fn main() {
     println!("[== AOCL ==]");
+    let args = std::env::args().collect::<Vec<_>>();
+    assert!((2..=4).contains(&args.len()));
+
+    // AOCL reads `AOCL_DYNAMIC` at BLIS initialisation (the first call); children inherit it. The environment is
+    // only modified while the process is single-threaded, before the rayon pool starts.
+    if let Some(toggle) = args.get(3) {
+        let value = match toggle.as_str() {
+            "dynamic-on" => "1",
+            "dynamic-off" => "0",
+            _ => panic!("Unknown AOCL_DYNAMIC toggle: {toggle}"),
+        };
+        std::env::set_var("AOCL_DYNAMIC", value);
+    }
+
     rayon::ThreadPoolBuilder::new().num_threads(4).build_global().unwrap();
 
-    let args = std::env::args().collect::<Vec<_>>();
-    assert!(args.len() == 2);
     let mode = &args[1];
+    let interface = args.get(2).map_or("fortran", String::as_str);
+    println!("dgemm interface: {interface}");
+    INTERFACE.set(interface.to_string()).unwrap();
+    println!("AOCL_DYNAMIC: {}", aocl_dynamic());
 
     match mode.as_str() {
+        "outer-set" => test_outer_set(),
         "inner-set" => test_inner_set(),
         "inner-set-lapack" => test_inner_set_lapack(),
+        "outer-gomp-set" => test_outer_gomp_set(),
+        "inner-gomp-set" => test_inner_gomp_set(),
+        "inner-gomp-set-lapack" => test_inner_gomp_set_lapack(),
+        "dynamic" => test_dynamic(),
+        "sweep" => test_sweep("dgemm"),
+        "sweep-gemv" => test_sweep("dgemv"),
+        "sweep-lapack" => test_sweep("dsyevd"),
+        m if m.starts_with("mixed-") => test_mixed(m.strip_prefix("mixed-").unwrap()),
+        m if m.starts_with("steal-") => test_steal(m.strip_prefix("steal-").unwrap()),
+        m if m.starts_with("scoped-") || m.starts_with("persistent-") => {
+            let (kind, rest) = m.split_once('-').unwrap();
+            let (place, controller) = rest.split_once('-').unwrap();
+            test_threads(kind, place, controller)
+        },
+        m if m.starts_with("pool-") => {
+            let (case, controller) = m.strip_prefix("pool-").unwrap().split_once('-').unwrap();
+            test_pool(case, controller)
+        },
+        m if m.starts_with("tokio-") => test_tokio(m.strip_prefix("tokio-").unwrap()),
+        m if m.starts_with("main-concurrent-") => test_main_concurrent(m.strip_prefix("main-concurrent-").unwrap()),
+        "fork" => test_fork(),
+        "reload" => test_reload(),
+        "reload-cycle" => test_reload_cycle(),
+        m if m.starts_with("ways-") => test_ways(m.strip_prefix("ways-").unwrap()),
         _ => panic!("Unknown mode: {mode}"),
     }
 }
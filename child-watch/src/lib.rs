@@ -0,0 +1,79 @@
+//! Waiting on a child process with a timeout, and dumping where it hangs.
+//!
+//! Shared by the fork and reload scenarios of the backend crates and by `scenario-runner`.
+
+use std::time::{Duration, Instant};
+
+/// How a waited-on child ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exit {
+    Code(i32),
+    Signal(i32),
+    /// Still running at the timeout; its process tree was dumped and killed.
+    Timeout,
+}
+
+/// Process ids of `pid` and all its descendants, from `/proc/<pid>/task/*/children`.
+pub fn process_tree(pid: i32) -> Vec<i32> {
+    let mut tree = vec![pid];
+    let mut i = 0;
+    while i < tree.len() {
+        let Ok(tasks) = std::fs::read_dir(format!("/proc/{}/task", tree[i])) else {
+            i += 1;
+            continue;
+        };
+        for task in tasks.flatten() {
+            let children = std::fs::read_to_string(task.path().join("children")).unwrap_or_default();
+            tree.extend(children.split_whitespace().filter_map(|s| s.parse::<i32>().ok()));
+        }
+        i += 1;
+    }
+    tree
+}
+
+/// Print name, `wchan` and kernel stack (when readable) of every thread of the process tree rooted at `pid`, each
+/// line prefixed with `tag`.
+pub fn dump_threads(pid: i32, tag: &str) {
+    for pid in process_tree(pid) {
+        let cmdline = std::fs::read_to_string(format!("/proc/{pid}/cmdline")).unwrap_or_default().replace('\0', " ");
+        println!("{tag} process {pid}: {}", cmdline.trim_end());
+        let Ok(tasks) = std::fs::read_dir(format!("/proc/{pid}/task")) else { continue };
+        for task in tasks.flatten() {
+            let path = task.path();
+            let comm = std::fs::read_to_string(path.join("comm")).unwrap_or_default();
+            let wchan = std::fs::read_to_string(path.join("wchan")).unwrap_or_default();
+            println!("{tag}   thread {} ({}), wchan: {wchan}", task.file_name().to_string_lossy(), comm.trim_end());
+            // the kernel stack is only readable with CAP_SYS_ADMIN
+            if let Ok(stack) = std::fs::read_to_string(path.join("stack")) {
+                for frame in stack.lines() {
+                    println!("{tag}     {frame}");
+                }
+            }
+        }
+    }
+}
+
+/// Wait for the child `pid` to exit. After `timeout`, dump the threads of its process tree under `tag`, kill the
+/// whole tree and reap the child.
+pub fn wait_timeout(pid: i32, timeout: Duration, tag: &str) -> Exit {
+    let time = Instant::now();
+    let mut status = 0;
+    while unsafe { libc::waitpid(pid, &mut status, libc::WNOHANG) } != pid {
+        if time.elapsed() > timeout {
+            println!("{tag} child {pid} still running after {timeout:?}, threads of its process tree:");
+            dump_threads(pid, tag);
+            // grandchildren are not reaped here; once killed they are reparented and reaped by init
+            for pid in process_tree(pid) {
+                unsafe { libc::kill(pid, libc::SIGKILL) };
+            }
+            unsafe { libc::waitpid(pid, &mut status, 0) };
+            return Exit::Timeout;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    if libc::WIFSIGNALED(status) {
+        Exit::Signal(libc::WTERMSIG(status))
+    } else {
+        Exit::Code(libc::WEXITSTATUS(status))
+    }
+}
@@ -1,21 +1,26 @@
-# source openblas-pthreads.env
-# target/debug/openblas-pthreads outer-openblas-set
-# target/debug/openblas-pthreads inner-openblas-set
-# target/debug/openblas-pthreads outer-openblas-set-local
-# target/debug/openblas-pthreads inner-openblas-set-local
-
-# source openblas-gomp.env
-# target/debug/openblas-gomp outer-gomp-set
-# target/debug/openblas-gomp inner-gomp-set
-# target/debug/openblas-gomp outer-openblas-set
-# target/debug/openblas-gomp inner-openblas-set
-# target/debug/openblas-gomp outer-openblas-set-local
-# target/debug/openblas-gomp inner-openblas-set-local
+# target/debug/netlib-dyload baseline
+# target/debug/netlib-dyload baseline-lapack
+
+# . ./openblas-pthreads.env && target/debug/openblas-pthreads outer-openblas-set
+# . ./openblas-pthreads.env && target/debug/openblas-pthreads inner-openblas-set
+# . ./openblas-pthreads.env && target/debug/openblas-pthreads outer-openblas-set-local
+# . ./openblas-pthreads.env && target/debug/openblas-pthreads inner-openblas-set-local
+
+# . ./openblas-gomp.env && target/debug/openblas-gomp outer-gomp-set
+# . ./openblas-gomp.env && target/debug/openblas-gomp inner-gomp-set
+# . ./openblas-gomp.env && target/debug/openblas-gomp outer-openblas-set
+# . ./openblas-gomp.env && target/debug/openblas-gomp inner-openblas-set
+# . ./openblas-gomp.env && target/debug/openblas-gomp outer-openblas-set-local
+# . ./openblas-gomp.env && target/debug/openblas-gomp inner-openblas-set-local
 
 # target/debug/openblas-pthreads-dyload outer-openblas-set
 # target/debug/openblas-pthreads-dyload inner-openblas-set
 # target/debug/openblas-pthreads-dyload outer-openblas-set-local
 # target/debug/openblas-pthreads-dyload inner-openblas-set-local
+# target/debug/openblas-pthreads-dyload outer-openblas-set ilp64
+# target/debug/openblas-pthreads-dyload inner-openblas-set ilp64
+# target/debug/openblas-pthreads-dyload outer-openblas-set-local ilp64
+# target/debug/openblas-pthreads-dyload inner-openblas-set-local ilp64
 
 # target/debug/openblas-gomp-dyload outer-gomp-set
 # target/debug/openblas-gomp-dyload inner-gomp-set
@@ -23,13 +28,101 @@
 # target/debug/openblas-gomp-dyload inner-openblas-set
 # target/debug/openblas-gomp-dyload outer-openblas-set-local
 # target/debug/openblas-gomp-dyload inner-openblas-set-local
+# target/debug/openblas-gomp-dyload outer-gomp-set ilp64
+# target/debug/openblas-gomp-dyload inner-gomp-set ilp64
+# target/debug/openblas-gomp-dyload inner-openblas-set-local ilp64
+# target/debug/openblas-gomp-dyload inner-gomp-set-lapack ilp64
+
+# target/debug/openblas-llvm-omp-dyload outer-gomp-set
+# target/debug/openblas-llvm-omp-dyload inner-gomp-set
+# target/debug/openblas-llvm-omp-dyload outer-openblas-set
+# target/debug/openblas-llvm-omp-dyload inner-openblas-set
+# target/debug/openblas-llvm-omp-dyload outer-openblas-set-local
+# target/debug/openblas-llvm-omp-dyload inner-openblas-set-local
+# target/debug/openblas-llvm-omp-dyload outer-gomp-set ilp64
+# target/debug/openblas-llvm-omp-dyload inner-gomp-set ilp64
+# target/debug/openblas-llvm-omp-dyload inner-openblas-set-local ilp64
+# target/debug/openblas-llvm-omp-dyload inner-gomp-set-lapack ilp64
 
 # target/debug/mkl-dyload outer-set
 # target/debug/mkl-dyload inner-set
 # target/debug/mkl-dyload outer-set-local
 # target/debug/mkl-dyload inner-set-local
+# target/debug/mkl-dyload inner-set-local cblas-col
+# target/debug/mkl-dyload inner-set-local cblas-row
+# target/debug/mkl-dyload inner-set fortran intel
+# target/debug/mkl-dyload inner-set-local fortran intel
+# target/debug/mkl-dyload inner-set fortran gnu
+# target/debug/mkl-dyload inner-set-local fortran gnu
+# target/debug/mkl-dyload inner-set fortran tbb
+# target/debug/mkl-dyload inner-set-local fortran tbb
+# target/debug/mkl-dyload inner-set fortran sequential
+# target/debug/mkl-dyload inner-set-local fortran sequential
+# target/debug/mkl-dyload outer-set fortran ilp64
+# target/debug/mkl-dyload inner-set fortran ilp64
+# target/debug/mkl-dyload inner-set-local fortran ilp64
+# target/debug/mkl-dyload inner-set-local-lapack fortran ilp64
+# target/debug/mkl-dyload inner-set-local fortran gnu ilp64
 
 target/debug/blis-gomp-dyload outer-gomp-set
 target/debug/blis-gomp-dyload inner-gomp-set
 target/debug/blis-gomp-dyload outer-blis-set
 target/debug/blis-gomp-dyload inner-blis-set
+# target/debug/blis-gomp-dyload inner-rntm
+# target/debug/blis-gomp-dyload ways-single
+# target/debug/blis-gomp-dyload ways-split
+# target/debug/blis-gomp-dyload outer-blis-set fortran single
+# target/debug/blis-gomp-dyload inner-blis-set fortran single
+# target/debug/blis-gomp-dyload outer-blis-set fortran openmp
+# target/debug/blis-gomp-dyload inner-blis-set fortran openmp
+# target/debug/blis-gomp-dyload outer-blis-set fortran pthreads
+# target/debug/blis-gomp-dyload inner-blis-set fortran pthreads
+# target/debug/blis-gomp-dyload outer-gomp-set ilp64
+# target/debug/blis-gomp-dyload inner-gomp-set ilp64
+# target/debug/blis-gomp-dyload outer-blis-set ilp64
+# target/debug/blis-gomp-dyload inner-blis-set ilp64
+
+# target/debug/aocl-dyload outer-set
+# target/debug/aocl-dyload inner-set
+# target/debug/aocl-dyload outer-gomp-set
+# target/debug/aocl-dyload inner-gomp-set
+# target/debug/aocl-dyload inner-set-lapack
+# target/debug/aocl-dyload inner-gomp-set-lapack
+# target/debug/aocl-dyload dynamic fortran dynamic-on
+# target/debug/aocl-dyload dynamic fortran dynamic-off
+# target/debug/aocl-dyload inner-set fortran dynamic-off
+# target/debug/aocl-dyload inner-gomp-set fortran dynamic-off
+
+# target/debug/kml-dyload inner-set-kml fortran omp
+# target/debug/kml-dyload inner-set-blas fortran omp
+# target/debug/kml-dyload inner-set-blas-lapack fortran omp
+# target/debug/kml-dyload inner-set-both-lapack fortran omp
+# target/debug/kml-dyload inner-set-kml fortran pthread
+# target/debug/kml-dyload inner-set-blas fortran pthread
+# target/debug/kml-dyload inner-set-blas-lapack fortran pthread
+# target/debug/kml-dyload inner-set-both-lapack fortran pthread
+# target/debug/kml-dyload inner-set-kml fortran locking
+# target/debug/kml-dyload inner-set-blas fortran locking
+# target/debug/kml-dyload inner-set-blas-lapack fortran locking
+# target/debug/kml-dyload inner-set-both-lapack fortran locking
+# target/debug/kml-dyload inner-set-kml fortran nolocking
+# target/debug/kml-dyload inner-set-blas fortran nolocking
+# target/debug/kml-dyload inner-set-blas-lapack fortran nolocking
+# target/debug/kml-dyload inner-set-both-lapack fortran nolocking
+# KML_ROOT=target/kml-root target/debug/kml-dyload inner-set-blas fortran omp
+# KML_ROOT=target/kml-root target/debug/kml-dyload inner-set-blas fortran pthread
+# KML_ROOT=target/kml-root target/debug/kml-dyload inner-set-blas fortran locking
+# KML_ROOT=target/kml-root target/debug/kml-dyload inner-set-blas fortran nolocking
+
+# target/debug/flexiblas-dyload backends
+# target/debug/flexiblas-dyload outer-set fortran OPENBLAS
+# target/debug/flexiblas-dyload inner-set fortran OPENBLAS
+
+# target/debug/faer-native outer-faer-set
+# target/debug/faer-native inner-faer-set
+# target/debug/faer-native inner-faer-seq
+# target/debug/faer-native inner-none
+# target/debug/faer-native inner-mm
+# target/debug/faer-native outer-faer-set-lapack
+# target/debug/faer-native inner-faer-seq-lapack
+# target/debug/faer-native inner-none-lapack
@@ -0,0 +1,276 @@
+use faer::diag::Diag;
+use faer::dyn_stack::{MemBuffer, MemStack};
+use faer::linalg::evd::{self_adjoint_evd, self_adjoint_evd_scratch, ComputeEigenvectors};
+use faer::linalg::matmul::matmul;
+use faer::{Accum, Mat, MatMut, MatRef, Par};
+use rayon::prelude::*;
+use std::sync::Mutex;
+
+/// Applies the thread controller named in mode strings: `faer-set` is faer's process-global parallelism; `faer-seq`
+/// (per-call `Par::Seq`), `mm` (matrixmultiply, sized by `MATMUL_NUM_THREADS` once) and `none` have no setter.
+fn set_num_threads(controller: &str, n: usize) {
+    match controller {
+        "none" | "faer-seq" | "mm" => (),
+        "faer-set" => faer::set_global_parallelism(if n == 1 { Par::Seq } else { Par::rayon(n) }),
+        _ => panic!("Unknown controller: {controller}"),
+    }
+}
+
+/// Parallelism the next faer call of the named controller runs with.
+fn par_for(controller: &str) -> Par {
+    match controller {
+        "faer-seq" => Par::Seq,
+        _ => faer::get_global_parallelism(),
+    }
+}
+
+/// Reads back the thread count that the named controller acts on. matrixmultiply exposes no getter, so for `mm`
+/// the count is derived rather than read back, and printed as such (see `threads_tag`).
+fn get_num_threads(controller: &str) -> usize {
+    match controller {
+        // as matrixmultiply 0.3 sizes its pool: unset or empty means the physical cores, unparsable means 1,
+        // and the result is clamped to 1..=4 (its `MAX_THREADS`)
+        "mm" => {
+            let threads = match std::env::var("MATMUL_NUM_THREADS") {
+                Ok(s) if !s.is_empty() => s.parse().unwrap_or(1),
+                _ => num_cpus::get_physical(),
+            };
+            threads.clamp(1, 4)
+        },
+        _ => par_for(controller).degree(),
+    }
+}
+
+/// Label of the thread counts printed for `controller`.
+fn threads_tag(controller: &str) -> String {
+    match controller {
+        "mm" => "mm, derived from MATMUL_NUM_THREADS".to_string(),
+        _ => controller.to_string(),
+    }
+}
+
+fn gen_mat() -> Vec<f64> {
+    (0..2048 * 2048).map(|x| x as f64 / 2048.0).collect()
+}
+
+fn gen_vecs() -> [Vec<Mutex<Vec<f64>>>; 3] {
+    let vec_a: Vec<Mutex<Vec<f64>>> = (0..16).map(|_| Mutex::new(gen_mat())).collect();
+    let vec_b: Vec<Mutex<Vec<f64>>> = (0..16).map(|_| Mutex::new(gen_mat())).collect();
+    let vec_c: Vec<Mutex<Vec<f64>>> = (0..16).map(|_| Mutex::new(gen_mat())).collect();
+    [vec_a, vec_b, vec_c]
+}
+
+/// `c = 3 a^T b` for column-major `n x n` matrices, through faer or matrixmultiply.
+fn run_blas(a: &[f64], b: &[f64], c: &mut [f64], controller: &str) {
+    let n = 2048;
+    let mut stat_p = perf_monitor::cpu::ProcessStat::cur().unwrap();
+    let time = std::time::Instant::now();
+    match controller {
+        "mm" => unsafe {
+            // a^T is `a` with swapped strides
+            let (a, b, c) = (a.as_ptr(), b.as_ptr(), c.as_mut_ptr());
+            let n = n as isize;
+            matrixmultiply::dgemm(n as _, n as _, n as _, 3.0, a, n, 1, b, 1, n, 0.0, c, 1, n);
+        },
+        _ => {
+            let a = MatRef::from_column_major_slice(a, n, n);
+            let b = MatRef::from_column_major_slice(b, n, n);
+            let c = MatMut::from_column_major_slice_mut(c, n, n);
+            matmul(c, Accum::Replace, a.transpose(), b, 3.0, par_for(controller));
+        },
+    }
+    let elapsed = time.elapsed();
+    let usage_p = stat_p.cpu().unwrap() * 100.0;
+    let thread_id = rayon::current_thread_index().unwrap_or(0);
+    println!("[CPU] rayon thread id {thread_id:2}, wall time: {elapsed:8.2?}, process usage: {usage_p:.2}%");
+}
+
+/// Eigendecomposition of the upper triangle of `a`, like `dsyevd` with `jobz = V`: `a` is overwritten by the
+/// eigenvectors and the eigenvalues are returned.
+fn run_lapack(a: &mut [f64], controller: &str) -> Result<Vec<f64>, i32> {
+    let n = 2048;
+    let par = par_for(controller);
+    let mut stat_p = perf_monitor::cpu::ProcessStat::cur().unwrap();
+    let time = std::time::Instant::now();
+    let mut u = Mat::<f64>::zeros(n, n);
+    let mut s = Diag::<f64>::zeros(n);
+    let mut mem = MemBuffer::new(self_adjoint_evd_scratch::<f64>(n, ComputeEigenvectors::Yes, par, Default::default()));
+    // faer reads the lower triangle, which is the upper triangle of the transpose
+    let a_ref = MatRef::from_column_major_slice(a, n, n).transpose();
+    let info = self_adjoint_evd(a_ref, s.as_mut(), Some(u.as_mut()), par, MemStack::new(&mut mem), Default::default());
+    let elapsed = time.elapsed();
+    let usage_p = stat_p.cpu().unwrap() * 100.0;
+    let thread_id = rayon::current_thread_index().unwrap_or(0);
+    println!("[LAPACK] rayon thread id {thread_id:2}, wall time: {elapsed:8.2?}, process usage: {usage_p:.2}%");
+    match info {
+        Ok(()) => {
+            a.chunks_mut(n)
+                .enumerate()
+                .for_each(|(j, col)| col.iter_mut().enumerate().for_each(|(i, x)| *x = u[(i, j)]));
+            Ok(s.column_vector().iter().copied().collect())
+        },
+        Err(_) => Err(1),
+    }
+}
+
+fn dot(x: &[f64], y: &[f64]) -> f64 {
+    let mut acc = [0.0; 4];
+    let mut x4 = x.chunks_exact(4);
+    let mut y4 = y.chunks_exact(4);
+    for (xs, ys) in (&mut x4).zip(&mut y4) {
+        acc.iter_mut().zip(xs).zip(ys).for_each(|((acc, x), y)| *acc += x * y);
+    }
+    let tail: f64 = x4.remainder().iter().zip(y4.remainder()).map(|(x, y)| x * y).sum();
+    acc.iter().sum::<f64>() + tail
+}
+
+/// Pure-Rust reference of `a^T * b` for column-major `n x n` matrices, blocked over columns and `k`.
+fn ref_gemm_tn(a: &[f64], b: &[f64], n: usize) -> Vec<f64> {
+    const NB: usize = 64;
+    const KB: usize = 256;
+    let mut c = vec![0.0; n * n];
+    c.par_chunks_mut(n * NB).enumerate().for_each(|(jb, c_blk)| {
+        for kb in (0..n).step_by(KB) {
+            let ke = (kb + KB).min(n);
+            for ib in (0..n).step_by(NB) {
+                for (jj, c_col) in c_blk.chunks_mut(n).enumerate() {
+                    let j = jb * NB + jj;
+                    let b_col = &b[j * n + kb..j * n + ke];
+                    for i in ib..(ib + NB).min(n) {
+                        c_col[i] += dot(&a[i * n + kb..i * n + ke], b_col);
+                    }
+                }
+            }
+        }
+    });
+    c
+}
+
+fn verify_blas(vec_c: &[Mutex<Vec<f64>>]) {
+    // all iterations multiply the same `gen_mat` inputs, so one reference serves every `c` of every scenario
+    static C_REF: std::sync::OnceLock<Vec<f64>> = std::sync::OnceLock::new();
+    let c_ref = C_REF.get_or_init(|| {
+        let a = gen_mat();
+        ref_gemm_tn(&a, &a, 2048).into_iter().map(|x| 3.0 * x).collect()
+    });
+    let c_max = c_ref.iter().fold(0.0_f64, |acc, x| acc.max(x.abs()));
+    for (i, c) in vec_c.iter().enumerate() {
+        let c = c.lock().unwrap();
+        let err = c.iter().zip(c_ref).fold(0.0_f64, |acc, (x, y)| acc.max((x - y).abs())) / c_max;
+        let status = if err < 1e-10 { "ok" } else { "FAILED" };
+        println!("[Verify] iter {i:2} dgemm max relative error: {err:.2e}, {status}");
+    }
+}
+
+fn norm_1(a: &[f64], n: usize) -> f64 {
+    a.chunks(n).map(|col| col.iter().map(|x| x.abs()).sum::<f64>()).fold(0.0, f64::max)
+}
+
+fn verify_lapack(vec_a: &[Mutex<Vec<f64>>], vec_w: &[Mutex<Result<Vec<f64>, i32>>]) {
+    // the eigensolver only reads the upper triangle, so the reference works on its symmetric expansion
+    let n = 2048;
+    let a0 = gen_mat();
+    let a_sym: Vec<f64> = (0..n * n).map(|x| (x % n, x / n)).map(|(r, c)| a0[r.min(c) + r.max(c) * n]).collect();
+    let a_norm = norm_1(&a_sym, n);
+    for (i, (v, w)) in vec_a.iter().zip(vec_w).enumerate() {
+        let w = w.lock().unwrap();
+        let w = match w.as_ref() {
+            Ok(w) => w,
+            Err(info) => {
+                println!("[Verify] iter {i:2} dsyevd info: {info}, FAILED");
+                continue;
+            },
+        };
+        let v = v.lock().unwrap();
+
+        // A V - V Λ, with A V computed as A^T V since A is symmetric
+        let mut res = ref_gemm_tn(&a_sym, &v, n);
+        res.par_chunks_mut(n).zip(v.par_chunks(n)).zip(w.par_iter()).for_each(|((r, v), w)| {
+            r.iter_mut().zip(v).for_each(|(r, v)| *r -= w * v);
+        });
+        let res = norm_1(&res, n) / (n as f64 * a_norm * f64::EPSILON);
+
+        // V^T V - I
+        let mut orth = ref_gemm_tn(&v, &v, n);
+        (0..n).for_each(|j| orth[j * n + j] -= 1.0);
+        let orth = norm_1(&orth, n) / (n as f64 * f64::EPSILON);
+
+        // same scaling and threshold as the LAPACK test suite (dsyt21)
+        let status = if res < 30.0 && orth < 30.0 { "ok" } else { "FAILED" };
+        println!("[Verify] iter {i:2} dsyevd residual: {res:.2e}, orthogonality: {orth:.2e}, {status}");
+    }
+}
+
+fn test_control(place: &str, controller: &str, routine: &str) {
+    println!("=== {place}, {controller} ({routine}) ===");
+
+    if place == "outer" {
+        set_num_threads(controller, 1);
+    }
+    let [vec_a, vec_b, vec_c] = gen_vecs();
+    let vec_w: Vec<Mutex<Result<Vec<f64>, i32>>> = (0..16).map(|_| Mutex::new(Err(0))).collect();
+    let mut stat_p = perf_monitor::cpu::ProcessStat::cur().unwrap();
+    let time = std::time::Instant::now();
+    (0..16).into_par_iter().for_each(|i| {
+        if place == "inner" {
+            set_num_threads(controller, 1);
+        }
+
+        let thread_id = rayon::current_thread_index().unwrap_or(0);
+        println!("[Thread] iter {i:2} start, rayon thread id: {thread_id:2}");
+
+        let num_threads = get_num_threads(controller);
+        println!("[Thread] iter {i:2} threads ({}): {num_threads}", threads_tag(controller));
+
+        match routine {
+            "dgemm" => {
+                let a = vec_a[i].lock().unwrap();
+                let b = vec_b[i].lock().unwrap();
+                let mut c = vec_c[i].lock().unwrap();
+                run_blas(&a, &b, &mut c, controller);
+            },
+            "dsyevd" => {
+                let mut a = vec_a[i].lock().unwrap();
+                *vec_w[i].lock().unwrap() = run_lapack(&mut a, controller);
+            },
+            _ => panic!("Unknown routine: {routine}"),
+        }
+    });
+    let elapsed = time.elapsed();
+    let usage_p = stat_p.cpu().unwrap() * 100.0;
+    let limit = rayon::current_num_threads() as f64 * 100.0;
+    let num_threads = get_num_threads(controller);
+    println!("[Process] threads after iteration ({}): {num_threads}", threads_tag(controller));
+    println!("[Process] {routine} region wall time: {elapsed:.2?}, usage: {usage_p:.2}%, rayon limit: {limit:.0}%");
+    if routine == "dgemm" {
+        let rate = 16.0 * 2.0 * 2048.0_f64.powi(3) / 1e9 / elapsed.as_secs_f64();
+        println!("[Process] region throughput: {rate:7.2} GFLOP/s");
+    }
+    let verdict = if usage_p <= limit * 1.1 { "effective" } else { "uncontrolled" };
+    println!("[Verdict] {place} {controller} ({routine}): {verdict}");
+    match routine {
+        "dgemm" => verify_blas(&vec_c),
+        _ => verify_lapack(&vec_a, &vec_w),
+    }
+}
+
+fn main() {
+    println!("[== faer / matrixmultiply ==]");
+    rayon::ThreadPoolBuilder::new().num_threads(4).build_global().unwrap();
+
+    let args = std::env::args().collect::<Vec<_>>();
+    assert!(args.len() == 2);
+    let mode = &args[1];
+
+    // `<outer|inner>-<controller>[-lapack]`, e.g. `inner-faer-seq-lapack`; matrixmultiply has no eigensolver
+    let (place, rest) = mode.split_once('-').unwrap_or_else(|| panic!("Unknown mode: {mode}"));
+    let (controller, routine) = match rest.strip_suffix("-lapack") {
+        Some(controller) if controller != "mm" => (controller, "dsyevd"),
+        Some(_) => panic!("Unknown mode: {mode}"),
+        None => (rest, "dgemm"),
+    };
+    match place {
+        "outer" | "inner" => test_control(place, controller, routine),
+        _ => panic!("Unknown mode: {mode}"),
+    }
+}
@@ -0,0 +1,13 @@
+//! `locking` kblas stand-in for `kml-stub`, laid out as `lib/kblas/locking/libkblas.so` by `kml-stub/make-root.sh`.
+//!
+//! It only exports the variant marker, which `libkml_stub.so` resolves against whichever stand-in was preloaded
+//! with `RTLD_GLOBAL`, as `libklapack_full.so` resolves its BLAS symbols against the preloaded kblas.
+
+#![allow(non_snake_case)]
+
+use std::ffi::c_char;
+
+#[no_mangle]
+pub extern "C" fn KmlStubVariant() -> *const c_char {
+    c"locking".as_ptr()
+}
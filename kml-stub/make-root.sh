@@ -0,0 +1,14 @@
+#!/bin/sh
+# Lay out the `kml-stub` stand-ins like a KML install under target/kml-root, for
+# `KML_ROOT=target/kml-root target/debug/kml-dyload <mode> <interface> <variant>` where KML is not available.
+# Build them first with `cargo build -p kml-stub -p kml-stub-kblas-omp -p kml-stub-kblas-pthread
+# -p kml-stub-kblas-locking -p kml-stub-kblas-nolocking` (or `cargo build --workspace`).
+set -e
+cd "$(dirname "$0")/.."
+lib=target/kml-root/lib
+for variant in omp pthread locking nolocking; do
+    mkdir -p "$lib/kblas/$variant"
+    ln -sf "$PWD/target/debug/libkml_stub_kblas_$variant.so" "$lib/kblas/$variant/libkblas.so"
+done
+ln -sf "$PWD/target/debug/libkml_stub.so" "$lib/libklapack_full.so"
+echo "KML_ROOT=$PWD/target/kml-root"
@@ -0,0 +1,255 @@
+//! Stand-in for the KML libraries where KML is not available (it only ships for Kunpeng/aarch64).
+//!
+//! Exports the symbols `kml-dyload` resolves, with one threading model for every kblas variant: BLAS calls
+//! use `BlasSetNumThreadsLocal` if set on the calling thread, else `BlasSetNumThreads`; `dsyevd_` uses
+//! `KmlSetNumThreads`. Work is parallelised by faer on the stub's own rayon pool.
+//!
+//! It stands in for `libklapack_full.so`: like it, it leaves a symbol undefined (`KmlStubVariant`) to be bound to
+//! the kblas stand-in (`kml-stub/kblas/<variant>`) preloaded with `RTLD_GLOBAL`, and reports the binding through
+//! `KmlStubKblasVariant`. `kml-stub/make-root.sh` lays the libraries out as a KML install.
+
+#![allow(non_snake_case)]
+#![allow(clippy::missing_safety_doc)]
+#![allow(clippy::too_many_arguments)]
+
+use faer::diag::Diag;
+use faer::dyn_stack::{MemBuffer, MemStack};
+use faer::linalg::evd::{self_adjoint_evd, self_adjoint_evd_scratch, ComputeEigenvectors};
+use faer::linalg::matmul::matmul;
+use faer::prelude::{Reborrow, ReborrowMut};
+use faer::{Accum, Mat, MatMut, MatRef, Par};
+use std::cell::Cell;
+use std::ffi::{c_char, c_int};
+use std::sync::atomic::{AtomicI32, Ordering};
+
+const CBLAS_ROW_MAJOR: c_int = 101;
+const CBLAS_NO_TRANS: c_int = 111;
+
+/// Thread counts set by the controllers; 0 means unset (all cores).
+static BLAS_THREADS: AtomicI32 = AtomicI32::new(0);
+static KML_THREADS: AtomicI32 = AtomicI32::new(0);
+
+thread_local! {
+    static BLAS_THREADS_LOCAL: Cell<c_int> = const { Cell::new(0) };
+}
+
+extern "C" {
+    /// Exported by the preloaded kblas stand-in; loading this library without one fails, as klapack does.
+    fn KmlStubVariant() -> *const c_char;
+}
+
+/// Variant of the kblas stand-in this library was bound to when it was loaded.
+#[no_mangle]
+pub unsafe extern "C" fn KmlStubKblasVariant() -> *const c_char {
+    KmlStubVariant()
+}
+
+fn all_cores() -> c_int {
+    std::thread::available_parallelism().map_or(1, |n| n.get() as c_int)
+}
+
+fn par(num_threads: c_int) -> Par {
+    if num_threads > 1 {
+        Par::rayon(num_threads as usize)
+    } else {
+        Par::Seq
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn BlasSetNumThreads(n: c_int) {
+    BLAS_THREADS.store(n, Ordering::Relaxed);
+}
+
+#[no_mangle]
+pub extern "C" fn BlasGetNumThreads() -> c_int {
+    match BLAS_THREADS.load(Ordering::Relaxed) {
+        0 => all_cores(),
+        n => n,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn BlasSetNumThreadsLocal(n: c_int) {
+    BLAS_THREADS_LOCAL.set(n);
+}
+
+#[no_mangle]
+pub extern "C" fn BlasGetNumThreadsLocal() -> c_int {
+    match BLAS_THREADS_LOCAL.get() {
+        0 => BlasGetNumThreads(),
+        n => n,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn KmlSetNumThreads(n: c_int) {
+    KML_THREADS.store(n, Ordering::Relaxed);
+}
+
+#[no_mangle]
+pub extern "C" fn KmlGetMaxThreads() -> c_int {
+    match KML_THREADS.load(Ordering::Relaxed) {
+        0 => all_cores(),
+        n => n,
+    }
+}
+
+/// `C = alpha A B + beta C` for `m x k` `A`, `k x n` `B` and `m x n` `C`, all given by (row, column) strides.
+unsafe fn gemm(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f64,
+    a: *const f64,
+    (rs_a, cs_a): (isize, isize),
+    b: *const f64,
+    (rs_b, cs_b): (isize, isize),
+    beta: f64,
+    c: *mut f64,
+    (rs_c, cs_c): (isize, isize),
+) {
+    let a = MatRef::from_raw_parts(a, m, k, rs_a, cs_a);
+    let b = MatRef::from_raw_parts(b, k, n, rs_b, cs_b);
+    let mut c = MatMut::from_raw_parts_mut(c, m, n, rs_c, cs_c);
+    // BLAS never reads `C` when `beta` is 0
+    let accum = if beta == 0.0 {
+        Accum::Replace
+    } else {
+        c.rb_mut().col_iter_mut().for_each(|col| col.iter_mut().for_each(|x| *x *= beta));
+        Accum::Add
+    };
+    matmul(c, accum, a, b, alpha, par(BlasGetNumThreadsLocal()));
+}
+
+/// Strides of `op(X)` for column-major `X` with leading dimension `ld`.
+fn strides(trans: bool, ld: c_int) -> (isize, isize) {
+    if trans {
+        (ld as isize, 1)
+    } else {
+        (1, ld as isize)
+    }
+}
+
+fn is_trans(t: c_char) -> bool {
+    matches!(t as u8, b'T' | b't' | b'C' | b'c')
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dgemm_(
+    transa: *const c_char,
+    transb: *const c_char,
+    m: *const c_int,
+    n: *const c_int,
+    k: *const c_int,
+    alpha: *const f64,
+    a: *const f64,
+    lda: *const c_int,
+    b: *const f64,
+    ldb: *const c_int,
+    beta: *const f64,
+    c: *mut f64,
+    ldc: *const c_int,
+) {
+    let (m, n, k) = (*m as usize, *n as usize, *k as usize);
+    let (sa, sb) = (strides(is_trans(*transa), *lda), strides(is_trans(*transb), *ldb));
+    gemm(m, n, k, *alpha, a, sa, b, sb, *beta, c, strides(false, *ldc));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dgemv_(
+    trans: *const c_char,
+    m: *const c_int,
+    n: *const c_int,
+    alpha: *const f64,
+    a: *const f64,
+    lda: *const c_int,
+    x: *const f64,
+    incx: *const c_int,
+    beta: *const f64,
+    y: *mut f64,
+    incy: *const c_int,
+) {
+    // `y` and `x` are single columns of a gemm with `op(A)` of `m x n` or `n x m`
+    let trans = is_trans(*trans);
+    let (rows, cols) = if trans { (*n as usize, *m as usize) } else { (*m as usize, *n as usize) };
+    if rows == 0 || cols == 0 {
+        return;
+    }
+    let (incx, incy) = (*incx as isize, *incy as isize);
+    let (x, y) = (x.offset(origin(cols, incx)), y.offset(origin(rows, incy)));
+    gemm(rows, 1, cols, *alpha, a, strides(trans, *lda), x, (incx, 0), *beta, y, (incy, 0));
+}
+
+/// Offset of element 0 of a BLAS vector of `len > 0` elements: with a negative increment it is stored last.
+fn origin(len: usize, inc: isize) -> isize {
+    if inc < 0 {
+        (1 - len as isize) * inc
+    } else {
+        0
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn cblas_dgemm(
+    layout: c_int,
+    transa: c_int,
+    transb: c_int,
+    m: c_int,
+    n: c_int,
+    k: c_int,
+    alpha: f64,
+    a: *const f64,
+    lda: c_int,
+    b: *const f64,
+    ldb: c_int,
+    beta: f64,
+    c: *mut f64,
+    ldc: c_int,
+) {
+    // a row-major matrix is its column-major transpose, so the row-major case swaps every stride pair
+    let row_major = layout == CBLAS_ROW_MAJOR;
+    let sa = strides((transa != CBLAS_NO_TRANS) != row_major, lda);
+    let sb = strides((transb != CBLAS_NO_TRANS) != row_major, ldb);
+    gemm(m as usize, n as usize, k as usize, alpha, a, sa, b, sb, beta, c, strides(row_major, ldc));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dsyevd_(
+    jobz: *const c_char,
+    uplo: *const c_char,
+    n: *const c_int,
+    a: *mut f64,
+    lda: *const c_int,
+    w: *mut f64,
+    work: *mut f64,
+    lwork: *mut c_int,
+    iwork: *mut c_int,
+    liwork: *mut c_int,
+    info: *mut c_int,
+) {
+    let n = *n as usize;
+    *info = 0;
+    if *lwork == -1 || *liwork == -1 {
+        // workspace query: the sizes LAPACK documents for `jobz = V`
+        *work = (1 + 6 * n + 2 * n * n) as f64;
+        *iwork = (3 + 5 * n) as c_int;
+        return;
+    }
+    let par = par(KmlGetMaxThreads());
+    let a_mat = MatMut::from_raw_parts_mut(a, n, n, 1, *lda as isize);
+    // faer reads the lower triangle, which is the upper triangle of the transpose
+    let a_ref = if matches!(*uplo as u8, b'U' | b'u') { a_mat.rb().transpose() } else { a_mat.rb() };
+    let mut u = Mat::<f64>::zeros(n, n);
+    let mut s = Diag::<f64>::zeros(n);
+    let mut mem = MemBuffer::new(self_adjoint_evd_scratch::<f64>(n, ComputeEigenvectors::Yes, par, Default::default()));
+    if self_adjoint_evd(a_ref, s.as_mut(), Some(u.as_mut()), par, MemStack::new(&mut mem), Default::default()).is_err()
+    {
+        *info = 1;
+        return;
+    }
+    s.column_vector().iter().enumerate().for_each(|(i, x)| *w.add(i) = *x);
+    if matches!(*jobz as u8, b'V' | b'v') {
+        MatMut::from_raw_parts_mut(a, n, n, 1, *lda as isize).copy_from(&u);
+    }
+}
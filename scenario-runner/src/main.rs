@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::os::unix::process::CommandExt;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// Region wall time from a `[Baseline] <routine> region wall time: <t>` or `[Process] <routine> region wall time: <t>`
+/// output line: whether it is a baseline, the routine, and the time in seconds.
+fn parse_region(line: &str) -> Option<(bool, String, f64)> {
+    let (head, rest) = line.split_once(" region wall time: ")?;
+    let (tag, routine) = head.split_once(' ')?;
+    let baseline = match tag {
+        "[Baseline]" => true,
+        "[Process]" => false,
+        _ => return None,
+    };
+    // `{:.2?}` of a `Duration`: a number followed by `s`, `ms`, `µs` or `ns`
+    let time = rest.split(',').next()?.trim();
+    let (value, unit) = time.split_at(time.find(|c: char| !(c.is_ascii_digit() || c == '.'))?);
+    let scale = match unit {
+        "s" => 1.0,
+        "ms" => 1e-3,
+        "µs" => 1e-6,
+        "ns" => 1e-9,
+        _ => return None,
+    };
+    Some((baseline, routine.to_string(), value.parse::<f64>().ok()? * scale))
+}
+
+/// Run one scenario line in a fresh process group; returns its outcome and the region wall times it reported.
+fn run_scenario(line: &str, timeout: Duration) -> (String, Vec<(bool, String, f64)>) {
+    // each line gets its own shell, so environment set on the line (`VAR=... cmd`, `. ./x.env && cmd`) stays local
+    // `wait_timeout` reaps the child by pid
+    #[allow(clippy::zombie_processes)]
+    let mut child = Command::new("bash").arg("-c").arg(line).process_group(0).stdout(Stdio::piped()).spawn().unwrap();
+    let pid = child.id();
+    // forward the output as it comes, keeping the region wall times
+    let stdout = BufReader::new(child.stdout.take().unwrap());
+    let reader = std::thread::spawn(move || {
+        let lines = stdout.lines().map_while(Result::ok).inspect(|line| println!("{line}"));
+        lines.filter_map(|line| parse_region(&line)).collect::<Vec<_>>()
+    });
+    let time = Instant::now();
+    let outcome = match child_watch::wait_timeout(pid as i32, timeout, "[Hang]") {
+        child_watch::Exit::Code(0) => format!("ok ({:.2?})", time.elapsed()),
+        child_watch::Exit::Code(code) => format!("exit code {code} ({:.2?})", time.elapsed()),
+        child_watch::Exit::Signal(signal) => format!("signal {signal} ({:.2?})", time.elapsed()),
+        child_watch::Exit::Timeout => format!("timeout ({timeout:?})"),
+    };
+    (outcome, reader.join().unwrap())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    assert!(args.len() == 2 || args.len() == 3, "Usage: {} <plan> [timeout-seconds]", args[0]);
+    let timeout = Duration::from_secs(args.get(2).map_or(60, |s| s.parse().unwrap()));
+
+    // plan: one scenario per line, blank lines and `#` comments skipped (`exec.sh` is one)
+    let plan = std::fs::read_to_string(&args[1]).unwrap();
+    let scenarios: Vec<&str> = plan.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')).collect();
+
+    let mut outcomes = vec![];
+    // latest netlib baseline per routine; a plan lists `netlib-dyload baseline*` first to get speedups
+    let mut baselines = HashMap::new();
+    for line in &scenarios {
+        println!("[Runner] >>> {line}");
+        let (outcome, regions) = run_scenario(line, timeout);
+        for (baseline, routine, secs) in regions {
+            if baseline {
+                baselines.insert(routine, secs);
+            } else if let Some(base) = baselines.get(&routine) {
+                println!("[Runner] {routine} speedup over netlib baseline: {:.2}x", base / secs);
+            }
+        }
+        println!("[Runner] <<< {line}: {outcome}");
+        outcomes.push(outcome);
+    }
+
+    println!("=== Summary ===");
+    for (line, outcome) in scenarios.iter().zip(&outcomes) {
+        println!("[Summary] {outcome:>24} | {line}");
+    }
+    let num_failed = outcomes.iter().filter(|o| !o.starts_with("ok")).count();
+    println!("[Summary] {} scenarios, {num_failed} failed or hung", scenarios.len());
+    if num_failed > 0 {
+        std::process::exit(1);
+    }
+}